@@ -1,13 +1,15 @@
-pub const COLUMN_USERNAME_SIZE: usize = 32;
-pub const COLUMN_EMAIL_SIZE: usize = 255;
-pub const ID_SIZE: usize = std::mem::size_of::<u32>();
-pub const USERNAME_SIZE: usize = std::mem::size_of::<[u8; 32]>();
-pub const EMAIL_SIZE: usize = std::mem::size_of::<[u8; 255]>();
-pub const ID_OFFSET: usize = 0;
-pub const USERNAME_OFFSET: usize = ID_OFFSET + ID_SIZE;
-pub const EMAIL_OFFSET: usize = USERNAME_OFFSET + USERNAME_SIZE;
-pub const ROW_SIZE: usize = ID_SIZE + USERNAME_SIZE + EMAIL_SIZE;
+//! Layout of the database file.
+//!
+//! The file has no header: it is the table's pages back to back, each
+//! `PAGE_SIZE` bytes. A page holds as many rows as fit, packed from its
+//! start, and the bytes after the last row slot are zero. Every page but the
+//! last is written in full; the last page is cut off after its last row, so
+//! the row count follows from the file length.
+//!
+//! Rows are laid out by `schema::TableSchema::users`: `id` as a little-endian
+//! `u32`, then `username` and `email` as UTF-8 padded with NUL bytes to their
+//! fixed sizes. Nothing depends on the platform's byte order or word size, so
+//! files move freely between machines.
+
 pub const PAGE_SIZE: usize = 4096;
 pub const TABLE_MAX_PAGES: usize = 100;
-pub const ROWS_PER_PAGE: usize = PAGE_SIZE / ROW_SIZE;
-pub const TABLE_MAX_ROWS: usize = ROWS_PER_PAGE * TABLE_MAX_PAGES;
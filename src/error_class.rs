@@ -0,0 +1,44 @@
+/// Broad kinds of errors, each with its own process exit code so scripts can
+/// tell them apart. Exit code 1 is left for command-line usage errors.
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum ErrorClass {
+    /// Input that could not be parsed: statements, meta commands, CSV.
+    Parse,
+    /// Well-formed input that breaks a limit or a validation rule.
+    Constraint,
+    Io,
+    /// The database file is not laid out the way rlite writes it.
+    Corruption,
+    /// A write against a database opened read-only.
+    ReadOnly,
+    /// Stopped by Ctrl-C or a statement timeout.
+    Interrupted,
+    /// A bug in rlite: a command panicked.
+    Internal,
+}
+
+impl ErrorClass {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            ErrorClass::Parse => 2,
+            ErrorClass::Constraint => 3,
+            ErrorClass::Io => 4,
+            ErrorClass::Corruption => 5,
+            ErrorClass::ReadOnly => 6,
+            ErrorClass::Interrupted => 7,
+            ErrorClass::Internal => 8,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            ErrorClass::Parse => "parse",
+            ErrorClass::Constraint => "constraint",
+            ErrorClass::Io => "io",
+            ErrorClass::Corruption => "corruption",
+            ErrorClass::ReadOnly => "readonly",
+            ErrorClass::Interrupted => "interrupted",
+            ErrorClass::Internal => "internal",
+        }
+    }
+}
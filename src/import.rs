@@ -0,0 +1,492 @@
+use crate::error_class::ErrorClass;
+use crate::schema::TableSchema;
+use crate::tokenizer::{PrepareOptions, Row};
+
+/// How `.import` reads its file.
+#[derive(Clone, Copy)]
+pub enum ImportFormat {
+    Csv {
+        separator: char,
+    },
+    /// A JSON array of objects, or JSON Lines: one object per line.
+    Json,
+}
+
+pub enum ImportError {
+    Io(std::io::Error),
+    UnterminatedQuote { line: usize },
+    InvalidJson { line: usize, message: &'static str },
+    UnknownColumn { column: String },
+    InvalidRecords { errors: Vec<String> },
+}
+
+impl From<std::io::Error> for ImportError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl ImportError {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            ImportError::Io(_) => ErrorClass::Io,
+            ImportError::UnterminatedQuote { .. }
+            | ImportError::InvalidJson { .. }
+            | ImportError::UnknownColumn { .. } => ErrorClass::Parse,
+            ImportError::InvalidRecords { .. } => ErrorClass::Constraint,
+        }
+    }
+}
+
+impl std::fmt::Display for ImportError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ImportError::Io(err) => write!(f, "IO Error: {err}"),
+            ImportError::UnterminatedQuote { line } => {
+                write!(f, "Unterminated quoted field starting on line {line}.")
+            }
+            ImportError::InvalidJson { line, message } => {
+                write!(f, "Invalid JSON on line {line}: {message}.")
+            }
+            ImportError::UnknownColumn { column } => {
+                write!(f, "Unknown column in header: '{column}'.")
+            }
+            ImportError::InvalidRecords { errors } => {
+                for err in errors {
+                    writeln!(f, "{err}")?;
+                }
+                write!(f, "Import aborted: {} invalid record(s).", errors.len())
+            }
+        }
+    }
+}
+
+pub struct Record<'a> {
+    pub line: usize,
+    pub source: &'a str,
+    pub fields: Vec<String>,
+}
+
+/// Splits CSV text into records.
+///
+/// Fields may be wrapped in double quotes, in which case they can contain the
+/// separator, newlines, and `""` as an escaped quote.
+pub fn parse_csv(text: &str, separator: char) -> Result<Vec<Record<'_>>, ImportError> {
+    let mut records = Vec::new();
+    let mut chars = text.char_indices().peekable();
+    let mut line = 1;
+
+    while chars.peek().is_some() {
+        let start_line = line;
+        let start = chars.peek().map_or(text.len(), |&(i, _)| i);
+        let mut end = text.len();
+        let mut fields = Vec::new();
+        let mut field = String::new();
+        let mut in_quotes = false;
+
+        while let Some((i, c)) = chars.next() {
+            if in_quotes {
+                match c {
+                    '"' if chars.peek().is_some_and(|&(_, next)| next == '"') => {
+                        chars.next();
+                        field.push('"');
+                    }
+                    '"' => in_quotes = false,
+                    '\n' => {
+                        line += 1;
+                        field.push(c);
+                    }
+                    _ => field.push(c),
+                }
+            } else if c == '"' && field.is_empty() {
+                in_quotes = true;
+            } else if c == separator {
+                fields.push(std::mem::take(&mut field));
+            } else if c == '\n' {
+                end = i;
+                line += 1;
+                break;
+            } else if c != '\r' {
+                field.push(c);
+            }
+        }
+
+        if in_quotes {
+            return Err(ImportError::UnterminatedQuote { line: start_line });
+        }
+        fields.push(field);
+
+        let source = text[start..end].trim_end_matches('\r');
+        if source.is_empty() {
+            continue;
+        }
+        records.push(Record {
+            line: start_line,
+            source,
+            fields,
+        });
+    }
+
+    Ok(records)
+}
+
+/// Reads rows from a CSV file.
+///
+/// A first record whose fields are all column names is treated as a header
+/// and decides the column order; otherwise columns are in schema order.
+/// Every record is validated before anything is returned, so a bad file
+/// imports nothing.
+pub fn read_csv(
+    path: &str,
+    separator: char,
+    options: &PrepareOptions,
+    schema: &TableSchema,
+) -> Result<Vec<Row>, ImportError> {
+    let text = std::fs::read_to_string(path)?;
+    let records = parse_csv(&text, separator)?;
+    let columns = schema.column_names();
+
+    let mut order = (0..columns.len()).collect::<Vec<_>>();
+    let mut body = &records[..];
+    if let Some(first) = records.first()
+        && first.fields[0].trim().parse::<u32>().is_err()
+    {
+        for (position, name) in first.fields.iter().enumerate() {
+            let name = name.trim();
+            let column = columns
+                .iter()
+                .position(|c| c.eq_ignore_ascii_case(name))
+                .ok_or_else(|| ImportError::UnknownColumn {
+                    column: name.to_string(),
+                })?;
+            if let Some(slot) = order.get_mut(column) {
+                *slot = position;
+            }
+        }
+        body = &records[1..];
+    }
+
+    let mut rows = Vec::with_capacity(body.len());
+    let mut errors = Vec::new();
+    for record in body {
+        if record.fields.len() != columns.len() {
+            errors.push(format!(
+                "{path}:{}: expected {} fields but found {}.",
+                record.line,
+                columns.len(),
+                record.fields.len()
+            ));
+            continue;
+        }
+
+        let values = order
+            .iter()
+            .map(|&i| record.fields[i].as_str().into())
+            .collect::<Vec<_>>();
+        match Row::from_values(&values, record.source, options, schema) {
+            Ok(row) => rows.push(row),
+            Err(e) => errors.push(format!("{path}:{}: {e}", record.line)),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(ImportError::InvalidRecords { errors });
+    }
+    Ok(rows)
+}
+
+enum Json {
+    Null,
+    Bool(bool),
+    /// Kept as written, so integer columns parse it like any other value.
+    Number(String),
+    String(String),
+    Array(Vec<Json>),
+    Object(Vec<(String, Json)>),
+}
+
+/// Nesting deeper than this is refused rather than risking the stack.
+const MAX_JSON_DEPTH: usize = 64;
+
+struct JsonParser<'a> {
+    text: &'a str,
+    pos: usize,
+    line: usize,
+}
+
+impl<'a> JsonParser<'a> {
+    fn error(&self, message: &'static str) -> ImportError {
+        ImportError::InvalidJson {
+            line: self.line,
+            message,
+        }
+    }
+
+    fn peek(&self) -> Option<u8> {
+        self.text.as_bytes().get(self.pos).copied()
+    }
+
+    fn skip_whitespace(&mut self) {
+        while let Some(c) = self.peek() {
+            match c {
+                b'\n' => self.line += 1,
+                b' ' | b'\t' | b'\r' => {}
+                _ => break,
+            }
+            self.pos += 1;
+        }
+    }
+
+    fn expect(&mut self, c: u8, message: &'static str) -> Result<(), ImportError> {
+        self.skip_whitespace();
+        if self.peek() != Some(c) {
+            return Err(self.error(message));
+        }
+        self.pos += 1;
+        Ok(())
+    }
+
+    fn value(&mut self, depth: usize) -> Result<Json, ImportError> {
+        if depth > MAX_JSON_DEPTH {
+            return Err(self.error("nested too deeply"));
+        }
+        self.skip_whitespace();
+        let rest = &self.text[self.pos..];
+        match self.peek() {
+            Some(b'{') => {
+                self.pos += 1;
+                let mut fields = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(b'}') {
+                    self.pos += 1;
+                    return Ok(Json::Object(fields));
+                }
+                loop {
+                    self.skip_whitespace();
+                    if self.peek() != Some(b'"') {
+                        return Err(self.error("expected a key"));
+                    }
+                    let key = self.string()?;
+                    self.expect(b':', "expected ':' after a key")?;
+                    fields.push((key, self.value(depth + 1)?));
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b'}') => {
+                            self.pos += 1;
+                            return Ok(Json::Object(fields));
+                        }
+                        _ => return Err(self.error("expected ',' or '}'")),
+                    }
+                }
+            }
+            Some(b'[') => {
+                self.pos += 1;
+                let mut items = Vec::new();
+                self.skip_whitespace();
+                if self.peek() == Some(b']') {
+                    self.pos += 1;
+                    return Ok(Json::Array(items));
+                }
+                loop {
+                    items.push(self.value(depth + 1)?);
+                    self.skip_whitespace();
+                    match self.peek() {
+                        Some(b',') => self.pos += 1,
+                        Some(b']') => {
+                            self.pos += 1;
+                            return Ok(Json::Array(items));
+                        }
+                        _ => return Err(self.error("expected ',' or ']'")),
+                    }
+                }
+            }
+            Some(b'"') => Ok(Json::String(self.string()?)),
+            Some(b'-' | b'0'..=b'9') => {
+                let len = rest
+                    .find(|c: char| !matches!(c, '0'..='9' | '-' | '+' | '.' | 'e' | 'E'))
+                    .unwrap_or(rest.len());
+                let number = &rest[..len];
+                if number.parse::<f64>().is_err() {
+                    return Err(self.error("malformed number"));
+                }
+                self.pos += len;
+                Ok(Json::Number(number.to_string()))
+            }
+            _ => {
+                for (word, value) in [
+                    ("true", Json::Bool(true)),
+                    ("false", Json::Bool(false)),
+                    ("null", Json::Null),
+                ] {
+                    if rest.starts_with(word) {
+                        self.pos += word.len();
+                        return Ok(value);
+                    }
+                }
+                Err(self.error("expected a value"))
+            }
+        }
+    }
+
+    /// Reads a string starting at its opening quote.
+    fn string(&mut self) -> Result<String, ImportError> {
+        self.pos += 1;
+        let mut string = String::new();
+        let mut chars = self.text[self.pos..].char_indices();
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '"' => {
+                    self.pos += i + 1;
+                    return Ok(string);
+                }
+                '\\' => {
+                    let escaped = match chars.next().map(|(_, c)| c) {
+                        Some('"') => '"',
+                        Some('\\') => '\\',
+                        Some('/') => '/',
+                        Some('b') => '\u{8}',
+                        Some('f') => '\u{c}',
+                        Some('n') => '\n',
+                        Some('r') => '\r',
+                        Some('t') => '\t',
+                        Some('u') => {
+                            let mut code = hex_escape(&mut chars)
+                                .ok_or_else(|| self.error("bad \\u escape"))?;
+                            // A high surrogate must be followed by its low half.
+                            if (0xd800..0xdc00).contains(&code) {
+                                let low = (chars.next().map(|(_, c)| c) == Some('\\')
+                                    && chars.next().map(|(_, c)| c) == Some('u'))
+                                .then(|| hex_escape(&mut chars))
+                                .flatten()
+                                .filter(|low| (0xdc00..0xe000).contains(low))
+                                .ok_or_else(|| self.error("unpaired surrogate"))?;
+                                code = 0x10000 + ((code - 0xd800) << 10) + (low - 0xdc00);
+                            }
+                            char::from_u32(code).ok_or_else(|| self.error("unpaired surrogate"))?
+                        }
+                        _ => return Err(self.error("bad escape")),
+                    };
+                    string.push(escaped);
+                }
+                '\n' => return Err(self.error("unterminated string")),
+                c if c.is_control() => return Err(self.error("control character in a string")),
+                c => string.push(c),
+            }
+        }
+        Err(self.error("unterminated string"))
+    }
+}
+
+/// Reads the four hex digits of a `\u` escape.
+fn hex_escape(chars: &mut std::str::CharIndices) -> Option<u32> {
+    let digits = (0..4)
+        .map(|_| chars.next().map(|(_, c)| c).filter(char::is_ascii_hexdigit))
+        .collect::<Option<String>>()?;
+    u32::from_str_radix(&digits, 16).ok()
+}
+
+/// Reads rows from a JSON file: an array of objects, or JSON Lines.
+///
+/// Keys name columns, in any case and any order, and every column must be
+/// present. Numbers and booleans are accepted for text columns and strings
+/// for integer columns; their text is checked like a statement's value.
+/// As with CSV, every record is validated before anything is returned.
+pub fn read_json(
+    path: &str,
+    options: &PrepareOptions,
+    schema: &TableSchema,
+) -> Result<Vec<Row>, ImportError> {
+    let text = std::fs::read_to_string(path)?;
+    let mut parser = JsonParser {
+        text: &text,
+        pos: 0,
+        line: 1,
+    };
+
+    // Each record with the line it starts on and its source text.
+    let mut records = Vec::new();
+    parser.skip_whitespace();
+    let array = parser.peek() == Some(b'[');
+    if array {
+        parser.pos += 1;
+    }
+    loop {
+        parser.skip_whitespace();
+        match parser.peek() {
+            None if !array => break,
+            Some(b']') if array && records.is_empty() => {
+                parser.pos += 1;
+                break;
+            }
+            _ => {}
+        }
+        let (line, start) = (parser.line, parser.pos);
+        let value = parser.value(0)?;
+        records.push((line, &text[start..parser.pos], value));
+        if array {
+            parser.skip_whitespace();
+            match parser.peek() {
+                Some(b',') => parser.pos += 1,
+                Some(b']') => {
+                    parser.pos += 1;
+                    break;
+                }
+                _ => return Err(parser.error("expected ',' or ']'")),
+            }
+        }
+    }
+    parser.skip_whitespace();
+    if parser.peek().is_some() {
+        return Err(parser.error("unexpected text after the array"));
+    }
+
+    let columns = schema.column_names();
+    let mut rows = Vec::with_capacity(records.len());
+    let mut errors = Vec::new();
+    'records: for (line, source, value) in &records {
+        let Json::Object(fields) = value else {
+            errors.push(format!("{path}:{line}: expected an object."));
+            continue;
+        };
+        let mut values = vec![None; columns.len()];
+        for (key, value) in fields {
+            let Some(column) = columns.iter().position(|c| c.eq_ignore_ascii_case(key)) else {
+                errors.push(format!("{path}:{line}: unknown column '{key}'."));
+                continue 'records;
+            };
+            values[column] = Some(match value {
+                Json::String(text) | Json::Number(text) => text.clone(),
+                Json::Bool(value) => value.to_string(),
+                Json::Null | Json::Array(_) | Json::Object(_) => {
+                    errors.push(format!(
+                        "{path}:{line}: '{key}' must be a string, number or boolean."
+                    ));
+                    continue 'records;
+                }
+            });
+        }
+        if let Some(missing) = values.iter().position(Option::is_none) {
+            errors.push(format!(
+                "{path}:{line}: missing column '{}'.",
+                columns[missing]
+            ));
+            continue;
+        }
+
+        let values = values
+            .iter()
+            .flatten()
+            .map(|value| value.as_str().into())
+            .collect::<Vec<_>>();
+        match Row::from_values(&values, source, options, schema) {
+            Ok(row) => rows.push(row),
+            Err(e) => errors.push(format!("{path}:{line}: {e}")),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(ImportError::InvalidRecords { errors });
+    }
+    Ok(rows)
+}
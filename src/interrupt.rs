@@ -0,0 +1,59 @@
+use std::sync::atomic::{AtomicBool, AtomicI32, Ordering};
+
+static INTERRUPTED: AtomicBool = AtomicBool::new(false);
+static SHUTDOWN: AtomicI32 = AtomicI32::new(0);
+
+#[cfg(unix)]
+mod sys {
+    pub const SIGHUP: i32 = 1;
+    pub const SIGINT: i32 = 2;
+    pub const SIGTERM: i32 = 15;
+
+    unsafe extern "C" {
+        pub fn signal(signum: i32, handler: extern "C" fn(i32)) -> usize;
+        pub fn siginterrupt(signum: i32, flag: i32) -> i32;
+    }
+}
+
+#[cfg(unix)]
+extern "C" fn on_sigint(_: i32) {
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Stops the running statement and asks the REPL to flush and exit.
+#[cfg(unix)]
+extern "C" fn on_shutdown(signum: i32) {
+    SHUTDOWN.store(signum, Ordering::SeqCst);
+    INTERRUPTED.store(true, Ordering::SeqCst);
+}
+
+/// Routes Ctrl-C to the interrupt flag, and SIGTERM/SIGHUP to a graceful
+/// shutdown, instead of killing the process.
+///
+/// Blocking reads are interrupted rather than restarted, so the REPL notices
+/// these signals while waiting at the prompt too.
+pub fn install() {
+    #[cfg(unix)]
+    // SAFETY: the handlers only store to atomics, which is async-signal-safe.
+    unsafe {
+        sys::signal(sys::SIGINT, on_sigint);
+        sys::siginterrupt(sys::SIGINT, 1);
+        for signum in [sys::SIGTERM, sys::SIGHUP] {
+            sys::signal(signum, on_shutdown);
+            sys::siginterrupt(signum, 1);
+        }
+    }
+}
+
+/// The signal that requested a shutdown, if any.
+pub fn shutdown_signal() -> Option<i32> {
+    match SHUTDOWN.load(Ordering::SeqCst) {
+        0 => None,
+        signum => Some(signum),
+    }
+}
+
+/// Clears the flag, returning whether it was set.
+pub fn take() -> bool {
+    INTERRUPTED.swap(false, Ordering::SeqCst)
+}
@@ -0,0 +1,63 @@
+//! The storage engine behind the `rlite` binary: statement parsing, the row
+//! format, and the pager. The REPL, meta commands and output modes live in
+//! the binary.
+//!
+//! `fuzz_prepare` and `fuzz_open` are the entry points of the cargo-fuzz
+//! harnesses under `fuzz/`.
+
+pub mod constants;
+pub mod error_class;
+pub mod pager;
+pub mod progress;
+pub mod schema;
+pub mod tokenizer;
+
+use pager::Table;
+use schema::TableSchema;
+use tokenizer::{InputBuffer, PrepareOptions, Statement};
+
+/// Splits arbitrary bytes into statements the way the REPL does and prepares
+/// each of them, in strict and in permissive mode.
+pub fn fuzz_prepare(data: &[u8]) {
+    let Ok(text) = std::str::from_utf8(data) else {
+        return;
+    };
+    let schema = TableSchema::users();
+    let permissive = PrepareOptions {
+        check_email: false,
+        strict: false,
+    };
+
+    let mut buffer = InputBuffer::default();
+    let mut statements = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        statements.extend(buffer.push_line(line, i + 1));
+    }
+    statements.extend(buffer.take_incomplete());
+    for (_, statement) in statements {
+        for options in [PrepareOptions::default(), permissive] {
+            let _ = Statement::prepare_statement(&statement, &options, &schema);
+        }
+    }
+}
+
+/// Loads arbitrary bytes as a database file and reads every row back, the
+/// way `select` and `rlite check` do.
+pub fn fuzz_open(data: &[u8]) {
+    let path = std::env::temp_dir().join(format!("rlite-fuzz-open-{}.db", std::process::id()));
+    if std::fs::write(&path, data).is_err() {
+        return;
+    }
+
+    if let Ok(mut table) = Table::open_readonly(&path)
+        && let Ok(rows) = table.rows()
+    {
+        for row in rows {
+            if row.validate().is_ok() {
+                row.values();
+            }
+        }
+    }
+    let _ = Table::check(&path);
+    let _ = std::fs::remove_file(&path);
+}
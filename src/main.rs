@@ -1,108 +1,788 @@
 #![allow(dead_code)]
-mod constants;
-mod pager;
-mod tokenizer;
+mod import;
+mod interrupt;
+mod meta;
+mod output;
+mod session;
+mod sqlite;
+mod subcommand;
 
-use constants::TABLE_MAX_ROWS;
-use pager::{Cursor, Table};
-use tokenizer::{Row, Statement, StatementType, do_meta_command};
+use rlite::{constants, error_class, pager, progress, schema, tokenizer};
+
+use error_class::ErrorClass;
+use import::{ImportError, ImportFormat};
+use meta::{MAX_SCRIPT_DEPTH, MetaCommand, MetaCommandError, do_meta_command};
+use output::{Mode, OutputSettings, json_string};
+use pager::{PageError, Table};
+use schema::{ColumnType, TableSchema};
+use session::Session;
+use std::time::{Duration, Instant};
+use tokenizer::{
+    DeserializeError, InputBuffer, PrepareError, Row, RowRef, Statement, StatementType,
+};
 
 enum ExecuteError {
     TableFull,
+    ReadOnly,
+    Interrupted,
+    Timeout(Duration),
+    Io(std::io::Error),
+    Page(PageError),
+    /// A row read during a scan, counting from 0, is damaged.
+    Corrupt(usize, DeserializeError),
+    /// The command panicked; carries the panic message.
+    Panicked(String),
+    /// A write after an earlier command panicked.
+    Poisoned,
+}
+
+impl ExecuteError {
+    fn class(&self) -> ErrorClass {
+        match self {
+            ExecuteError::TableFull => ErrorClass::Constraint,
+            ExecuteError::ReadOnly => ErrorClass::ReadOnly,
+            ExecuteError::Interrupted | ExecuteError::Timeout(_) => ErrorClass::Interrupted,
+            ExecuteError::Io(_) => ErrorClass::Io,
+            ExecuteError::Page(err) => err.class(),
+            ExecuteError::Corrupt(_, err) => err.class(),
+            ExecuteError::Panicked(_) | ExecuteError::Poisoned => ErrorClass::Internal,
+        }
+    }
 }
 
 impl std::fmt::Display for ExecuteError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
             ExecuteError::TableFull => write!(f, "Table full."),
+            ExecuteError::ReadOnly => write!(f, "Database is open read-only."),
+            ExecuteError::Interrupted => write!(f, "Interrupted."),
+            ExecuteError::Timeout(after) => {
+                write!(f, "Statement timed out after {} ms.", after.as_millis())
+            }
+            ExecuteError::Io(err) => write!(f, "IO Error: {err}"),
+            ExecuteError::Page(err) => write!(f, "{err}"),
+            ExecuteError::Corrupt(row, err) => write!(f, "Row {row} is corrupt: {err}."),
+            ExecuteError::Panicked(message) => write!(
+                f,
+                "Internal error: {message}\nWrites are disabled for the rest of the session; rows inserted before this are saved on exit."
+            ),
+            ExecuteError::Poisoned => write!(
+                f,
+                "Writes are disabled after an internal error. Reopen the database to write to it."
+            ),
+        }
+    }
+}
+
+enum ReplError<'a> {
+    Meta(MetaCommandError<'a>),
+    Prepare(PrepareError<'a>),
+    Execute(ExecuteError),
+    Import(ImportError),
+}
+
+impl ReplError<'_> {
+    fn class(&self) -> ErrorClass {
+        match self {
+            ReplError::Meta(err) => err.class(),
+            ReplError::Prepare(err) => err.class(),
+            ReplError::Execute(err) => err.class(),
+            ReplError::Import(err) => err.class(),
         }
     }
 }
 
-fn read_input() -> String {
-    use std::io::Write;
-    let mut line = String::new();
+impl std::fmt::Display for ReplError<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ReplError::Meta(err) => write!(f, "{err}"),
+            ReplError::Prepare(err) => write!(f, "{err}"),
+            ReplError::Execute(err) => write!(f, "{err}"),
+            ReplError::Import(err) => write!(f, "{err}"),
+        }
+    }
+}
 
-    print!("db> ");
-    let _ = std::io::stdout()
-        .flush()
-        .map_err(|e| println!("Unable to flush prompt: {e}"));
-    let _ = std::io::stdin()
-        .read_line(&mut line)
-        .map_err(|e| println!("Unable to read line: {e}"));
-    line.trim().to_string()
+/// Prints an error as text on stdout, or as a JSON object on stderr with
+/// `--json-errors`. `location` says where the failing command came from.
+fn report_error(session: &Session, location: Option<&str>, e: &ReplError) {
+    if session.json_errors {
+        let class = e.class();
+        let location = location.map_or(String::new(), |location| {
+            format!(",\"location\":{}", json_string(location))
+        });
+        eprintln!(
+            "{{\"class\":\"{}\",\"code\":{},\"message\":{}{location}}}",
+            class.name(),
+            class.exit_code(),
+            json_string(&e.to_string())
+        );
+    } else if let Some(location) = location {
+        println!("{location}: {e}");
+    } else {
+        println!("{e}");
+    }
 }
 
-fn execute_insert(statement: &Statement, table: &mut Table) -> Result<(), ExecuteError> {
-    if table.num_rows >= TABLE_MAX_ROWS {
-        return Err(ExecuteError::TableFull);
+enum Flow {
+    Continue,
+    Exit(i32),
+}
+
+enum Input {
+    Line(String),
+    /// Ctrl-C was pressed while waiting for input.
+    Interrupted,
+    Eof,
+}
+
+/// Reads one line from stdin. The prompt is only shown when stdin is a terminal.
+///
+/// Lines are read off the buffered stdin by hand because `read_line` retries
+/// interrupted reads, which would hide Ctrl-C at the prompt.
+fn read_input(prompt: &str) -> Input {
+    use std::io::{BufRead, IsTerminal, Write};
+    let mut line = Vec::new();
+
+    if std::io::stdin().is_terminal() {
+        print!("{prompt}");
+        let _ = std::io::stdout()
+            .flush()
+            .map_err(|e| println!("Unable to flush prompt: {e}"));
     }
 
-    let mut cursor = Cursor::from_end(table);
-    let row = cursor.value();
-    if let Some(row_to_insert) = &statement.row {
-        row.copy_from_slice(&row_to_insert.serialize());
-        table.num_rows += 1;
+    let mut stdin = std::io::stdin().lock();
+    loop {
+        if interrupt::shutdown_signal().is_some() {
+            return Input::Eof;
+        }
+        let buf = match stdin.fill_buf() {
+            Ok(buf) => buf,
+            Err(e) if e.kind() == std::io::ErrorKind::Interrupted => {
+                if interrupt::shutdown_signal().is_some() {
+                    return Input::Eof;
+                }
+                if interrupt::take() {
+                    return Input::Interrupted;
+                }
+                continue;
+            }
+            Err(e) => {
+                println!("Unable to read line: {e}");
+                return Input::Eof;
+            }
+        };
+
+        if buf.is_empty() {
+            if line.is_empty() {
+                return Input::Eof;
+            }
+            break;
+        }
+        match buf.iter().position(|&b| b == b'\n') {
+            Some(i) => {
+                line.extend_from_slice(&buf[..i]);
+                stdin.consume(i + 1);
+                break;
+            }
+            None => {
+                let len = buf.len();
+                line.extend_from_slice(buf);
+                stdin.consume(len);
+            }
+        }
     }
 
+    Input::Line(
+        String::from_utf8_lossy(&line)
+            .trim_end_matches('\r')
+            .to_string(),
+    )
+}
+
+fn insert_row(row: &Row, table: &mut Table) -> Result<(), ExecuteError> {
+    if table.readonly {
+        return Err(ExecuteError::ReadOnly);
+    }
+    if table.num_rows >= table.schema().max_rows() {
+        return Err(ExecuteError::TableFull);
+    }
+
+    table.append_slot().copy_from_slice(row.serialize());
+
     Ok(())
 }
 
-fn execute_select(table: &mut Table) {
-    let mut cursor = Cursor::from_start(table);
+fn execute_insert(statement: &Statement, table: &mut Table) -> Result<usize, ExecuteError> {
+    match &statement.row {
+        Some(row) => insert_row(row, table).map(|()| 1),
+        None => Ok(0),
+    }
+}
+
+/// Checks whether a running statement should stop, either because it was
+/// interrupted or because it ran past its deadline.
+fn check_running(deadline: Option<(Instant, Duration)>) -> Result<(), ExecuteError> {
+    if interrupt::take() {
+        return Err(ExecuteError::Interrupted);
+    }
+    match deadline {
+        Some((deadline, timeout)) if Instant::now() >= deadline => {
+            Err(ExecuteError::Timeout(timeout))
+        }
+        _ => Ok(()),
+    }
+}
+
+fn check_row(row: RowRef, index: usize) -> Result<(), ExecuteError> {
+    row.validate().map_err(|e| ExecuteError::Corrupt(index, e))
+}
 
-    while !cursor.end_of_table {
-        Row::deserialize(cursor.value()).print();
-        cursor.advance();
+/// The table's rows with their positions. With `skip_corrupt`, pages holding
+/// a damaged row are left out, and each one skipped is reported on stderr.
+fn scan(
+    table: &mut Table,
+    skip_corrupt: bool,
+) -> Result<impl Iterator<Item = (usize, RowRef<'_>)>, ExecuteError> {
+    let mut skipped = Vec::new();
+    let rows_per_page = table.schema().rows_per_page();
+    if skip_corrupt {
+        for (index, row) in table.rows().map_err(ExecuteError::Page)?.enumerate() {
+            let page = index / rows_per_page;
+            if skipped.last() == Some(&page) {
+                continue;
+            }
+            if let Err(e) = row.validate() {
+                eprintln!("Skipping page {page}: row {index} is corrupt: {e}.");
+                skipped.push(page);
+            }
+        }
     }
+
+    Ok(table
+        .rows()
+        .map_err(ExecuteError::Page)?
+        .enumerate()
+        .filter(move |(index, _)| !skipped.contains(&(index / rows_per_page))))
 }
 
-fn execute_statement(statement: &Statement, table: &mut Table) -> Result<(), ExecuteError> {
+fn execute_select(
+    session: &mut Session,
+    deadline: Option<(Instant, Duration)>,
+) -> Result<usize, ExecuteError> {
+    let schema = session.table.schema().clone();
+    let mut count = 0;
+    let mut stopped = None;
+    let rows = scan(&mut session.table, session.skip_corrupt)?.map_while(|(index, row)| {
+        if let Err(e) = check_running(deadline).and_then(|()| check_row(row, index)) {
+            stopped = Some(e);
+            return None;
+        }
+        count += 1;
+        Some(row)
+    });
+
+    session
+        .output
+        .emit(&schema, rows)
+        .map_err(ExecuteError::Io)?;
+    match stopped {
+        Some(e) => Err(e),
+        None => Ok(count),
+    }
+}
+
+/// The number of rows, which the file length gives without reading them.
+/// Skipping corrupt pages takes a scan to find out which ones are skipped.
+fn count_rows(session: &mut Session) -> Result<usize, ExecuteError> {
+    if session.skip_corrupt {
+        return Ok(scan(&mut session.table, true)?.count());
+    }
+    Ok(session.table.num_rows)
+}
+
+/// Prints the row count as a one-column result, in the current output mode.
+fn execute_count(session: &mut Session) -> Result<usize, ExecuteError> {
+    let count = count_rows(session)?;
+    let schema = TableSchema::new("count", &[("count(*)", ColumnType::Integer)]);
+    let bytes = (count as u32).to_le_bytes();
+    // Safe unwrap: the schema is a single integer, four bytes long
+    let row = RowRef::new(&bytes, &schema).unwrap();
+    session
+        .output
+        .emit(&schema, std::iter::once(row))
+        .map_err(ExecuteError::Io)?;
+    Ok(1)
+}
+
+/// One step of a statement's execution, as reported by `explain analyze`.
+struct Stage {
+    name: String,
+    rows: usize,
+    elapsed: Duration,
+}
+
+/// Runs a statement stage by stage, printing the rows each stage produced and
+/// the time it took instead of the statement's results.
+fn explain_analyze(
+    statement: &Statement,
+    session: &mut Session,
+    deadline: Option<(Instant, Duration)>,
+) -> Result<usize, ExecuteError> {
+    let mut stages = Vec::new();
     match statement.stype {
-        StatementType::Insert => execute_insert(statement, table),
+        StatementType::Insert => {
+            let start = Instant::now();
+            let rows = execute_insert(statement, &mut session.table)?;
+            stages.push(Stage {
+                name: format!("insert {}", session.table.schema().name),
+                rows,
+                elapsed: start.elapsed(),
+            });
+        }
         StatementType::Select => {
-            execute_select(table);
-            Ok(())
+            let start = Instant::now();
+            let mut scanned = Duration::ZERO;
+            let mut count = 0;
+            let mut stopped = None;
+            let schema = session.table.schema().clone();
+            let mut rows = scan(&mut session.table, session.skip_corrupt)?;
+            let rows = std::iter::from_fn(|| {
+                if let Err(e) = check_running(deadline) {
+                    stopped = Some(e);
+                    return None;
+                }
+                let start = Instant::now();
+                let (index, row) = rows.next()?;
+                if let Err(e) = check_row(row, index) {
+                    stopped = Some(e);
+                    return None;
+                }
+                scanned += start.elapsed();
+                count += 1;
+                Some(row)
+            });
+            // Rows are rendered as usual but thrown away, to time the formatting.
+            session
+                .output
+                .write_rows(&schema, rows, &mut std::io::sink())
+                .map_err(ExecuteError::Io)?;
+            if let Some(e) = stopped {
+                return Err(e);
+            }
+
+            stages.push(Stage {
+                name: format!("scan {}", schema.name),
+                rows: count,
+                elapsed: scanned,
+            });
+            stages.push(Stage {
+                name: format!("format {}", session.output.mode.name()),
+                rows: count,
+                elapsed: start.elapsed().saturating_sub(scanned),
+            });
+        }
+        StatementType::Count => {
+            let start = Instant::now();
+            count_rows(session)?;
+            stages.push(Stage {
+                name: format!("count {}", session.table.schema().name),
+                rows: 1,
+                elapsed: start.elapsed(),
+            });
         }
     }
+
+    let rows = stages.last().map_or(0, |stage| stage.rows);
+    let total = stages.iter().map(|stage| stage.elapsed).sum::<Duration>();
+    println!("{:<16} {:>6} {:>10}", "stage", "rows", "time");
+    for stage in &stages {
+        println!(
+            "{:<16} {:>6} {:>9.6}s",
+            stage.name,
+            stage.rows,
+            stage.elapsed.as_secs_f64()
+        );
+    }
+    println!("{:<16} {rows:>6} {:>9.6}s", "total", total.as_secs_f64());
+
+    Ok(rows)
 }
 
-fn main() {
-    let args = std::env::args().collect::<Vec<String>>();
-    if args.len() < 2 {
-        println!("Must supply a database filename.");
-        std::process::exit(0);
+/// Runs a prepared statement, returning how many rows it inserted or returned.
+fn execute_statement(statement: &Statement, session: &mut Session) -> Result<usize, ExecuteError> {
+    let deadline = session
+        .timeout
+        .map(|timeout| (Instant::now() + timeout, timeout));
+    if session.poisoned && matches!(statement.stype, StatementType::Insert) {
+        return Err(ExecuteError::Poisoned);
+    }
+    if statement.analyze {
+        return explain_analyze(statement, session, deadline);
     }
+    match statement.stype {
+        StatementType::Insert => execute_insert(statement, &mut session.table),
+        StatementType::Select => execute_select(session, deadline),
+        StatementType::Count => execute_count(session),
+    }
+}
 
-    let mut table = match Table::new(&args[1]) {
-        Ok(t) => t,
-        Err(e) => panic!("{e}"),
-    };
+/// Runs a single complete command, either a meta command or a statement.
+fn run_input<'a>(input: &'a str, session: &mut Session) -> Result<Flow, ReplError<'a>> {
+    if session.echo {
+        let terminator = if input.starts_with('.') { "" } else { ";" };
+        println!("{input}{terminator}");
+    }
 
-    loop {
-        let input = read_input();
+    let start = Instant::now();
+    let result = std::panic::catch_unwind(std::panic::AssertUnwindSafe(|| {
+        dispatch_input(input, session)
+    }))
+    .unwrap_or_else(|panic| {
+        session.poisoned = true;
+        Err(ReplError::Execute(ExecuteError::Panicked(panic_message(
+            &*panic,
+        ))))
+    });
+    if let Some(log) = &mut session.log {
+        let outcome = match &result {
+            Ok((_, rows)) => Ok(*rows),
+            Err(e) => Err(e.to_string()),
+        };
+        if let Err(e) = log.record(input, outcome, start.elapsed()) {
+            println!("Unable to write to log: {e}");
+        }
+    }
+    result.map(|(flow, _)| flow)
+}
+
+fn panic_message(panic: &(dyn std::any::Any + Send)) -> String {
+    match panic.downcast_ref::<&str>() {
+        Some(message) => message.to_string(),
+        None => panic
+            .downcast_ref::<String>()
+            .cloned()
+            .unwrap_or_else(|| "unknown panic".to_string()),
+    }
+}
+
+/// Runs a command, returning how many rows it affected when that applies.
+fn dispatch_input<'a>(
+    input: &'a str,
+    session: &mut Session,
+) -> Result<(Flow, Option<usize>), ReplError<'a>> {
+    if input.starts_with('.') {
+        let meta = do_meta_command(input, session).map_err(ReplError::Meta)?;
+        return match meta {
+            MetaCommand::Exit(code) => Ok((Flow::Exit(code), None)),
+            MetaCommand::Done => Ok((Flow::Continue, None)),
+            MetaCommand::Read(path) => run_script(&path, session)
+                .map(|flow| (flow, None))
+                .map_err(ReplError::Meta),
+            MetaCommand::Import { path, format } => {
+                let (options, schema) = (&session.prepare, session.table.schema());
+                let rows = match format {
+                    ImportFormat::Csv { separator } => {
+                        import::read_csv(&path, separator, options, schema)
+                    }
+                    ImportFormat::Json => import::read_json(&path, options, schema),
+                }
+                .map_err(ReplError::Import)?;
+                if session.poisoned {
+                    return Err(ReplError::Execute(ExecuteError::Poisoned));
+                }
+                let table = &mut session.table;
+                if table.readonly {
+                    return Err(ReplError::Execute(ExecuteError::ReadOnly));
+                }
+                if table.num_rows + rows.len() > table.schema().max_rows() {
+                    return Err(ReplError::Execute(ExecuteError::TableFull));
+                }
+                let mut progress = progress::Progress::new("Importing", rows.len());
+                let inserted = table
+                    .insert_many(rows.iter().inspect(|_| progress.tick()))
+                    .map_err(|e| ReplError::Execute(ExecuteError::Page(e)))?;
+                drop(progress);
+                println!("Imported {inserted} rows.");
+                Ok((Flow::Continue, Some(inserted)))
+            }
+        };
+    }
 
-        if input.starts_with('.') {
-            match do_meta_command(&input) {
-                Ok(()) => break,
+    interrupt::take();
+    let start = Instant::now();
+    let statement = Statement::prepare_statement(input, &session.prepare, session.table.schema())
+        .map_err(ReplError::Prepare)?;
+    let result = execute_statement(&statement, session).map_err(ReplError::Execute);
+    if session.timer {
+        println!("Run Time: real {:.6}", start.elapsed().as_secs_f64());
+    }
+    let rows = result?;
+    // Only the tuple mode reports completion; other modes print just the rows.
+    if session.output.mode == Mode::Tuple {
+        println!("Executed.");
+    }
+    Ok((Flow::Continue, Some(rows)))
+}
+
+/// Executes every command in a script file, reporting errors with the line
+/// number the failing command started on. A shutdown signal stops it before
+/// the next command, the way it stops the REPL.
+fn run_script<'a>(path: &str, session: &mut Session) -> Result<Flow, MetaCommandError<'a>> {
+    let script = std::fs::read_to_string(path)?;
+    let canonical = std::fs::canonicalize(path)?;
+    if session.scripts.contains(&canonical) {
+        return Err(MetaCommandError::RecursiveScript {
+            path: path.to_string(),
+        });
+    }
+    if session.scripts.len() >= MAX_SCRIPT_DEPTH {
+        return Err(MetaCommandError::ScriptsTooDeep);
+    }
+
+    session.scripts.push(canonical);
+    let result = run_script_lines(path, &script, session);
+    session.scripts.pop();
+    result
+}
+
+fn run_script_lines<'a>(
+    path: &str,
+    script: &str,
+    session: &mut Session,
+) -> Result<Flow, MetaCommandError<'a>> {
+    let mut buffer = InputBuffer::default();
+
+    for (i, line) in script.lines().enumerate() {
+        for (line_number, input) in buffer.push_line(line, i + 1) {
+            if let Some(signum) = interrupt::shutdown_signal() {
+                return Ok(Flow::Exit(128 + signum));
+            }
+            match run_input(&input, session) {
+                Ok(Flow::Continue) => {}
+                Ok(Flow::Exit(code)) => return Ok(Flow::Exit(code)),
+                Err(ReplError::Execute(ExecuteError::Interrupted)) => {
+                    println!("{path}:{line_number}: Interrupted, stopping script.");
+                    return Ok(Flow::Continue);
+                }
                 Err(e) => {
-                    println!("{e}");
-                    continue;
+                    report_error(session, Some(&format!("{path}:{line_number}")), &e);
+                    if session.bail {
+                        let class = e.class();
+                        return Err(MetaCommandError::ScriptAborted {
+                            path: path.to_string(),
+                            class,
+                        });
+                    }
                 }
             }
         }
+    }
+    if let Some((line_number, input)) = buffer.take_incomplete() {
+        println!("{path}:{line_number}: Incomplete statement, missing ';': '{input}'.");
+    }
 
-        let statement = match Statement::prepare_statement(&input) {
-            Ok(s) => s,
+    Ok(Flow::Continue)
+}
+
+/// Runs a command passed on the command line, where the final `;` is optional.
+/// Returns the exit code of the first error (0 if there was none), or the
+/// exit code requested with `.exit` or by a shutdown signal.
+fn run_argument(text: &str, session: &mut Session) -> Result<i32, i32> {
+    let mut buffer = InputBuffer::default();
+    let mut inputs = Vec::new();
+    for (i, line) in text.lines().enumerate() {
+        inputs.extend(buffer.push_line(line, i + 1));
+    }
+    inputs.extend(buffer.take_incomplete());
+
+    let mut status = 0;
+    for (_, input) in inputs {
+        if let Some(signum) = interrupt::shutdown_signal() {
+            return Err(128 + signum);
+        }
+        match run_input(&input, session) {
+            Ok(Flow::Continue) => {}
+            Ok(Flow::Exit(code)) => return Err(code),
             Err(e) => {
-                println!("{e}");
+                report_error(session, None, &e);
+                if status == 0 {
+                    status = e.class().exit_code();
+                }
+                if session.bail {
+                    break;
+                }
+            }
+        }
+    }
+    Ok(status)
+}
+
+/// Reads commands from stdin until end of input, returning the exit code.
+fn repl(session: &mut Session) -> i32 {
+    use std::io::IsTerminal;
+    let interactive = std::io::stdin().is_terminal();
+    let mut buffer = InputBuffer::default();
+    let mut line_number = 0;
+
+    'repl: loop {
+        let line = match read_input(if buffer.is_empty() { "db> " } else { "...> " }) {
+            Input::Line(line) => line,
+            Input::Eof => break,
+            Input::Interrupted if buffer.is_empty() => {
+                println!();
+                break;
+            }
+            Input::Interrupted => {
+                // Ctrl-C abandons a partially typed statement.
+                buffer = InputBuffer::default();
+                println!();
                 continue;
             }
         };
+        line_number += 1;
+        for (line_number, input) in buffer.push_line(&line, line_number) {
+            if interrupt::shutdown_signal().is_some() {
+                break 'repl;
+            }
+            match run_input(&input, session) {
+                Ok(Flow::Continue) => {}
+                Ok(Flow::Exit(code)) => return code,
+                Err(e) if interactive => report_error(session, None, &e),
+                Err(e) => {
+                    report_error(session, Some(&format!("line {line_number}")), &e);
+                    if session.bail {
+                        return e.class().exit_code();
+                    }
+                }
+            }
+        }
+    }
+    if let Some((line_number, input)) = buffer.take_incomplete() {
+        println!("line {line_number}: Incomplete statement, missing ';': '{input}'.");
+    }
+
+    0
+}
 
-        match execute_statement(&statement, &mut table) {
-            Ok(()) => println!("Executed."),
-            Err(e) => println!("{e}"),
+fn main() {
+    let args = std::env::args().skip(1).collect::<Vec<_>>();
+    if let Some((name, rest)) = args.split_first()
+        && subcommand::SUBCOMMANDS.contains(&name.as_str())
+    {
+        std::process::exit(subcommand::run(name, rest));
+    }
+
+    let mut output = OutputSettings::default();
+    let mut filename = None;
+    let mut bail = false;
+    let mut json_errors = false;
+    let mut timeout = None;
+    let mut init_file = None;
+    let mut init_commands = Vec::new();
+    let mut commands = Vec::new();
+    let mut args = args.into_iter();
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => output.mode = Mode::Json,
+            "-bail" => bail = true,
+            "--json-errors" => json_errors = true,
+            "--init" => match args.next() {
+                Some(path) => init_file = Some(path),
+                None => {
+                    println!("Missing argument for '--init'.");
+                    std::process::exit(1);
+                }
+            },
+            "--timeout-ms" => match args.next().map(|ms| ms.parse::<u64>()) {
+                Some(Ok(ms)) => timeout = (ms > 0).then(|| Duration::from_millis(ms)),
+                _ => {
+                    println!("'--timeout-ms' needs a number of milliseconds.");
+                    std::process::exit(1);
+                }
+            },
+            "-cmd" => match args.next() {
+                Some(command) => init_commands.push(command),
+                None => {
+                    println!("Missing argument for '-cmd'.");
+                    std::process::exit(1);
+                }
+            },
+            _ if arg.starts_with('-') => {
+                println!("Unrecognized option: '{arg}'.");
+                std::process::exit(1);
+            }
+            _ if filename.is_none() => filename = Some(arg),
+            _ => commands.push(arg),
         }
     }
+    let Some(filename) = filename else {
+        println!("Must supply a database filename.");
+        std::process::exit(0);
+    };
+
+    let table = match Table::new(&filename) {
+        Ok(t) => t,
+        Err(e) => {
+            println!("Could not open {filename}: {e}");
+            std::process::exit(e.class().exit_code());
+        }
+    };
+    let mut session = Session::new(table, output);
+    session.bail = bail;
+    session.json_errors = json_errors;
+    session.timeout = timeout;
+
+    interrupt::install();
+    // The init file (or ~/.rliterc when none is given) runs before anything else.
+    let init_file = init_file.or_else(|| {
+        let rc = std::path::Path::new(&std::env::var_os("HOME")?).join(".rliterc");
+        rc.is_file().then(|| rc.to_string_lossy().into_owned())
+    });
+    let mut exit_code = 0;
+    let mut exited = false;
+    if let Some(path) = &init_file {
+        match run_script(path, &mut session) {
+            Ok(Flow::Continue) => {}
+            Ok(Flow::Exit(code)) => {
+                exit_code = code;
+                exited = true;
+            }
+            Err(e) => report_error(&session, Some(path), &ReplError::Meta(e)),
+        }
+    }
+
+    // `-cmd` commands run next; commands given as arguments replace the REPL
+    // and make any failure show up in the exit status.
+    for command in init_commands.iter().chain(&commands) {
+        if exited {
+            break;
+        }
+        match run_argument(command, &mut session) {
+            Ok(0) => {}
+            Ok(code) if session.bail => {
+                exit_code = code;
+                exited = true;
+            }
+            Ok(code) if exit_code == 0 => exit_code = code,
+            Ok(_) => {}
+            Err(code) => {
+                exit_code = code;
+                exited = true;
+                break;
+            }
+        }
+    }
+    if commands.is_empty() && !exited {
+        exit_code = repl(&mut session);
+    }
+
+    if let Err(e) = session.table.flush() {
+        println!("Unable to flush database: {e}");
+        std::process::exit(1);
+    }
+    if let Some(signum) = interrupt::shutdown_signal() {
+        std::process::exit(128 + signum);
+    }
+    std::process::exit(exit_code);
 }
@@ -0,0 +1,895 @@
+use crate::constants::{PAGE_SIZE, TABLE_MAX_PAGES};
+use crate::error_class::ErrorClass;
+use crate::import::ImportFormat;
+use crate::output::{MAX_WIDTH, Mode, OutputFile};
+use crate::pager::{PageError, Table};
+use crate::schema::TableSchema;
+use crate::session::{LogFile, Session};
+use crate::tokenizer::{RowRef, Tokens};
+use std::borrow::Cow;
+use std::io::Write;
+
+/// How many scripts `.read` may nest, counting the outermost one.
+pub const MAX_SCRIPT_DEPTH: usize = 64;
+
+pub enum MetaCommandError<'a> {
+    UnrecognizedCommand {
+        meta: String,
+        input: &'a str,
+    },
+    Usage {
+        usage: &'static str,
+        input: &'a str,
+    },
+    InvalidArgument {
+        arg: String,
+        input: &'a str,
+    },
+    NoSuchTable {
+        table: String,
+    },
+    /// Carries the class of the error the script stopped at.
+    ScriptAborted {
+        path: String,
+        class: ErrorClass,
+    },
+    /// `.read` of a script that is already being read, directly or not.
+    RecursiveScript {
+        path: String,
+    },
+    /// `.read` nested deeper than `MAX_SCRIPT_DEPTH`.
+    ScriptsTooDeep,
+    Io(std::io::Error),
+    Page(PageError),
+}
+
+impl From<PageError> for MetaCommandError<'_> {
+    fn from(value: PageError) -> Self {
+        Self::Page(value)
+    }
+}
+
+impl From<std::io::Error> for MetaCommandError<'_> {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl MetaCommandError<'_> {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            MetaCommandError::UnrecognizedCommand { .. }
+            | MetaCommandError::Usage { .. }
+            | MetaCommandError::InvalidArgument { .. }
+            | MetaCommandError::NoSuchTable { .. } => ErrorClass::Parse,
+            MetaCommandError::ScriptAborted { class, .. } => *class,
+            MetaCommandError::RecursiveScript { .. } | MetaCommandError::ScriptsTooDeep => {
+                ErrorClass::Constraint
+            }
+            MetaCommandError::Io(_) => ErrorClass::Io,
+            MetaCommandError::Page(err) => err.class(),
+        }
+    }
+}
+
+impl std::fmt::Display for MetaCommandError<'_> {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            MetaCommandError::UnrecognizedCommand { meta, input } => {
+                write!(
+                    f,
+                    "Unrecognized command: '{meta}' in '{input}'\nUse '.help' to list commands."
+                )
+            }
+            MetaCommandError::Usage { usage, input } => {
+                write!(f, "Invalid arguments in '{input}'.\nUsage: {usage}")
+            }
+            MetaCommandError::InvalidArgument { arg, input } => {
+                write!(f, "Invalid argument: '{arg}' in '{input}'")
+            }
+            MetaCommandError::NoSuchTable { table } => {
+                write!(f, "No such table: '{table}'.")
+            }
+            MetaCommandError::RecursiveScript { path } => {
+                write!(f, "Script '{path}' is already being read.")
+            }
+            MetaCommandError::ScriptsTooDeep => {
+                write!(f, "Scripts nested more than {MAX_SCRIPT_DEPTH} deep.")
+            }
+            MetaCommandError::ScriptAborted { path, .. } => {
+                write!(f, "Script '{path}' stopped at the first error.")
+            }
+            MetaCommandError::Io(err) => write!(f, "IO Error: {err}"),
+            MetaCommandError::Page(err) => write!(f, "{err}"),
+        }
+    }
+}
+
+/// What the REPL has to do after a meta command ran.
+pub enum MetaCommand {
+    /// Leave the REPL with the given process exit code.
+    Exit(i32),
+    Done,
+    Read(String),
+    Import {
+        path: String,
+        format: ImportFormat,
+    },
+}
+
+/// The arguments of a meta command: positional values plus the `--name value`
+/// options and `--name` flags the command declares. Values may be quoted like
+/// statement values.
+pub struct Args<'a> {
+    input: &'a str,
+    positional: Vec<Cow<'a, str>>,
+    options: Vec<(&'static str, Cow<'a, str>)>,
+    flags: Vec<&'static str>,
+}
+
+impl<'a> Args<'a> {
+    fn parse(
+        command: &MetaCommandInfo,
+        mut tokens: Tokens<'a>,
+        input: &'a str,
+    ) -> Result<Self, MetaCommandError<'a>> {
+        let usage = || MetaCommandError::Usage {
+            usage: command.usage,
+            input,
+        };
+        let mut positional = Vec::new();
+        let mut options = Vec::new();
+        let mut flags = Vec::new();
+
+        while let Some(token) = tokens.next() {
+            if let Some(&option) = command.options.iter().find(|&&o| o == token) {
+                let value = tokens.next().ok_or_else(usage)?;
+                options.push((option, value));
+            } else if let Some(&flag) = command.flags.iter().find(|&&f| f == token) {
+                flags.push(flag);
+            } else {
+                positional.push(token);
+            }
+        }
+
+        if !command.args.contains(&positional.len()) {
+            return Err(usage());
+        }
+        Ok(Self {
+            input,
+            positional,
+            options,
+            flags,
+        })
+    }
+
+    fn get(&self, index: usize) -> Option<&str> {
+        self.positional.get(index).map(|arg| &**arg)
+    }
+
+    fn option(&self, name: &str) -> Option<&str> {
+        self.options
+            .iter()
+            .rev()
+            .find(|(option, _)| *option == name)
+            .map(|(_, value)| &**value)
+    }
+
+    fn flag(&self, name: &str) -> bool {
+        self.flags.contains(&name)
+    }
+
+    fn invalid(&self, arg: &str) -> MetaCommandError<'a> {
+        MetaCommandError::InvalidArgument {
+            arg: arg.to_string(),
+            input: self.input,
+        }
+    }
+
+    /// Parses an optional positional argument.
+    fn parse_at<T: std::str::FromStr>(
+        &self,
+        index: usize,
+    ) -> Result<Option<T>, MetaCommandError<'a>> {
+        self.get(index)
+            .map(|arg| arg.parse().map_err(|_| self.invalid(arg)))
+            .transpose()
+    }
+
+    /// Parses a required `on|off` argument.
+    fn switch(&self, index: usize) -> Result<bool, MetaCommandError<'a>> {
+        let value = self.get(index).unwrap_or_default();
+        match value {
+            "on" => Ok(true),
+            "off" => Ok(false),
+            _ => Err(self.invalid(value)),
+        }
+    }
+
+    /// Checks that an optional table argument names the table.
+    fn table(&self, index: usize, schema: &TableSchema) -> Result<(), MetaCommandError<'a>> {
+        match self.get(index) {
+            Some(table) if table != schema.name => Err(MetaCommandError::NoSuchTable {
+                table: table.to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+}
+
+type Handler = for<'a> fn(&Args<'a>, &mut Session) -> Result<MetaCommand, MetaCommandError<'a>>;
+
+pub struct MetaCommandInfo {
+    pub name: &'static str,
+    pub usage: &'static str,
+    pub help: &'static str,
+    /// How many positional arguments the command accepts.
+    args: std::ops::RangeInclusive<usize>,
+    /// Options that take a value, such as `--separator`.
+    options: &'static [&'static str],
+    /// Options that stand alone, such as `--readonly`.
+    flags: &'static [&'static str],
+    handler: Handler,
+}
+
+impl MetaCommandInfo {
+    /// Whether `name` refers to this command, with or without the leading dot.
+    fn matches(&self, name: &str) -> bool {
+        self.name == name || self.name.strip_prefix('.') == Some(name)
+    }
+}
+
+/// Every meta command, in the order `.help` lists them.
+pub const META_COMMANDS: &[MetaCommandInfo] = &[
+    MetaCommandInfo {
+        name: ".bail",
+        usage: ".bail on|off",
+        help: "Stop scripts and piped input at the first error",
+        args: 1..=1,
+        options: &[],
+        flags: &[],
+        handler: bail,
+    },
+    MetaCommandInfo {
+        name: ".clone",
+        usage: ".clone <file>",
+        help: "Copy the database into a new file",
+        args: 1..=1,
+        options: &[],
+        flags: &[],
+        handler: clone,
+    },
+    MetaCommandInfo {
+        name: ".constants",
+        usage: ".constants",
+        help: "Show the table's row layout and the page layout",
+        args: 0..=0,
+        options: &[],
+        flags: &[],
+        handler: show_constants,
+    },
+    MetaCommandInfo {
+        name: ".dbinfo",
+        usage: ".dbinfo",
+        help: "Show information about the database file",
+        args: 0..=0,
+        options: &[],
+        flags: &[],
+        handler: show_dbinfo,
+    },
+    MetaCommandInfo {
+        name: ".dump",
+        usage: ".dump [file]",
+        help: "Write the database as a script that recreates it",
+        args: 0..=1,
+        options: &[],
+        flags: &[],
+        handler: dump_to,
+    },
+    MetaCommandInfo {
+        name: ".echo",
+        usage: ".echo on|off",
+        help: "Print each command before running it",
+        args: 1..=1,
+        options: &[],
+        flags: &[],
+        handler: echo,
+    },
+    MetaCommandInfo {
+        name: ".emailcheck",
+        usage: ".emailcheck on|off",
+        help: "Require emails to look like local@domain",
+        args: 1..=1,
+        options: &[],
+        flags: &[],
+        handler: emailcheck,
+    },
+    MetaCommandInfo {
+        name: ".exit",
+        usage: ".exit [code]",
+        help: "Exit with the given status code (default 0)",
+        args: 0..=1,
+        options: &[],
+        flags: &[],
+        handler: exit,
+    },
+    MetaCommandInfo {
+        name: ".headers",
+        usage: ".headers on|off",
+        help: "Print column names in list and csv modes",
+        args: 1..=1,
+        options: &[],
+        flags: &[],
+        handler: headers,
+    },
+    MetaCommandInfo {
+        name: ".help",
+        usage: ".help [command]",
+        help: "List meta commands, or describe one",
+        args: 0..=1,
+        options: &[],
+        flags: &[],
+        handler: help,
+    },
+    MetaCommandInfo {
+        name: ".import",
+        usage: ".import <file> <table> [--separator <c>|--json]",
+        help: "Load rows from a CSV or JSON file",
+        args: 2..=2,
+        options: &["--separator"],
+        flags: &["--json"],
+        handler: import,
+    },
+    MetaCommandInfo {
+        name: ".log",
+        usage: ".log <file|off>",
+        help: "Append every command, its outcome and timing to a file",
+        args: 1..=1,
+        options: &[],
+        flags: &[],
+        handler: log,
+    },
+    MetaCommandInfo {
+        name: ".mode",
+        usage: ".mode <mode> [table]",
+        help: "Set the output mode: tuple, list, csv, json, column, table, insert",
+        args: 1..=2,
+        options: &[],
+        flags: &[],
+        handler: mode,
+    },
+    MetaCommandInfo {
+        name: ".nullvalue",
+        usage: ".nullvalue [text]",
+        help: "Set the text printed for NULL values",
+        args: 0..=1,
+        options: &[],
+        flags: &[],
+        handler: nullvalue,
+    },
+    MetaCommandInfo {
+        name: ".once",
+        usage: ".once <file>",
+        help: "Write the next query's results to a file",
+        args: 1..=1,
+        options: &[],
+        flags: &[],
+        handler: once,
+    },
+    MetaCommandInfo {
+        name: ".open",
+        usage: ".open [--readonly] [--new] <file>",
+        help: "Close the database and open another file",
+        args: 1..=1,
+        options: &[],
+        flags: &["--readonly", "--new"],
+        handler: open,
+    },
+    MetaCommandInfo {
+        name: ".output",
+        usage: ".output [file]",
+        help: "Write query results to a file, or back to stdout",
+        args: 0..=1,
+        options: &[],
+        flags: &[],
+        handler: output,
+    },
+    MetaCommandInfo {
+        name: ".pager",
+        usage: ".pager on|off",
+        help: "Page query results shown on a terminal through $PAGER",
+        args: 1..=1,
+        options: &[],
+        flags: &[],
+        handler: pager,
+    },
+    MetaCommandInfo {
+        name: ".permissive",
+        usage: ".permissive on|off",
+        help: "Ignore input left over after a complete statement",
+        args: 1..=1,
+        options: &[],
+        flags: &[],
+        handler: permissive,
+    },
+    MetaCommandInfo {
+        name: ".pragma",
+        usage: ".pragma [skip_corrupt[=on|off]]",
+        help: "Show or set a pragma; skip_corrupt leaves damaged pages out of scans",
+        args: 0..=1,
+        options: &[],
+        flags: &[],
+        handler: pragma,
+    },
+    MetaCommandInfo {
+        name: ".quit",
+        usage: ".quit [code]",
+        help: "Same as .exit",
+        args: 0..=1,
+        options: &[],
+        flags: &[],
+        handler: exit,
+    },
+    MetaCommandInfo {
+        name: ".read",
+        usage: ".read <file>",
+        help: "Run the commands in a script file",
+        args: 1..=1,
+        options: &[],
+        flags: &[],
+        handler: read,
+    },
+    MetaCommandInfo {
+        name: ".recover",
+        usage: ".recover <file>",
+        help: "Salvage readable rows from a damaged database into a new file",
+        args: 1..=1,
+        options: &[],
+        flags: &[],
+        handler: recover,
+    },
+    MetaCommandInfo {
+        name: ".schema",
+        usage: ".schema [table]",
+        help: "Show the CREATE TABLE statement",
+        args: 0..=1,
+        options: &[],
+        flags: &[],
+        handler: schema,
+    },
+    MetaCommandInfo {
+        name: ".separator",
+        usage: ".separator <text>",
+        help: "Set the column separator for list and csv modes",
+        args: 1..=1,
+        options: &[],
+        flags: &[],
+        handler: separator,
+    },
+    MetaCommandInfo {
+        name: ".show",
+        usage: ".show",
+        help: "Show the current settings",
+        args: 0..=0,
+        options: &[],
+        flags: &[],
+        handler: show,
+    },
+    MetaCommandInfo {
+        name: ".tablesize",
+        usage: ".tablesize <table>",
+        help: "Show a table's rows, pages and bytes without scanning it",
+        args: 1..=1,
+        options: &[],
+        flags: &[],
+        handler: tablesize,
+    },
+    MetaCommandInfo {
+        name: ".timeout_ms",
+        usage: ".timeout_ms <ms>",
+        help: "Stop statements that run longer than this; 0 turns it off",
+        args: 1..=1,
+        options: &[],
+        flags: &[],
+        handler: timeout_ms,
+    },
+    MetaCommandInfo {
+        name: ".timer",
+        usage: ".timer on|off",
+        help: "Print how long each statement takes",
+        args: 1..=1,
+        options: &[],
+        flags: &[],
+        handler: timer,
+    },
+    MetaCommandInfo {
+        name: ".width",
+        usage: ".width [n ...]",
+        help: "Set column widths for column and table modes (0 = automatic)",
+        args: 0..=usize::MAX,
+        options: &[],
+        flags: &[],
+        handler: width,
+    },
+];
+
+/// Looks the command up in `META_COMMANDS`, checks its arguments and runs it.
+pub fn do_meta_command<'a>(
+    input: &'a str,
+    session: &mut Session,
+) -> Result<MetaCommand, MetaCommandError<'a>> {
+    let mut tokens = Tokens::new(input);
+    let meta = tokens.next().unwrap_or_default();
+    let command = META_COMMANDS
+        .iter()
+        .find(|command| command.name == meta)
+        .ok_or_else(|| MetaCommandError::UnrecognizedCommand {
+            meta: meta.to_string(),
+            input,
+        })?;
+
+    let args = Args::parse(command, tokens, input)?;
+    (command.handler)(&args, session)
+}
+
+fn bail<'a>(args: &Args<'a>, session: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    session.bail = args.switch(0)?;
+    Ok(MetaCommand::Done)
+}
+
+fn clone<'a>(args: &Args<'a>, session: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    let path = args.get(0).unwrap_or_default();
+    let rows = session.table.backup_to(path)?;
+    println!("Copied {rows} rows to '{path}'.");
+    Ok(MetaCommand::Done)
+}
+
+fn recover<'a>(
+    args: &Args<'a>,
+    session: &mut Session,
+) -> Result<MetaCommand, MetaCommandError<'a>> {
+    let path = args.get(0).unwrap_or_default();
+    session.table.flush()?;
+    let recovery = Table::recover(session.table.path(), path)?;
+    println!(
+        "Recovered {} rows to '{path}'; skipped {} of {} pages.",
+        recovery.rows, recovery.pages_skipped, recovery.pages
+    );
+    Ok(MetaCommand::Done)
+}
+
+fn show_constants<'a>(
+    _: &Args<'a>,
+    session: &mut Session,
+) -> Result<MetaCommand, MetaCommandError<'a>> {
+    constants(session.table.schema(), &mut std::io::stdout().lock())?;
+    Ok(MetaCommand::Done)
+}
+
+fn show_dbinfo<'a>(
+    _: &Args<'a>,
+    session: &mut Session,
+) -> Result<MetaCommand, MetaCommandError<'a>> {
+    let mut out = std::io::stdout().lock();
+    dbinfo(&session.table, &mut out)?;
+    writeln!(out)?;
+    constants(session.table.schema(), &mut out)?;
+    Ok(MetaCommand::Done)
+}
+
+fn dump_to<'a>(
+    args: &Args<'a>,
+    session: &mut Session,
+) -> Result<MetaCommand, MetaCommandError<'a>> {
+    let schema = session.table.schema().clone();
+    match args.get(0) {
+        Some(path) => {
+            let mut file = std::io::BufWriter::new(std::fs::File::create(path)?);
+            dump(&schema, session.table.rows()?, &mut file)?;
+            file.flush()?;
+        }
+        None => dump(
+            &schema,
+            session.table.rows()?,
+            &mut std::io::stdout().lock(),
+        )?,
+    }
+    Ok(MetaCommand::Done)
+}
+
+fn echo<'a>(args: &Args<'a>, session: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    session.echo = args.switch(0)?;
+    Ok(MetaCommand::Done)
+}
+
+fn emailcheck<'a>(
+    args: &Args<'a>,
+    session: &mut Session,
+) -> Result<MetaCommand, MetaCommandError<'a>> {
+    session.prepare.check_email = args.switch(0)?;
+    Ok(MetaCommand::Done)
+}
+
+fn exit<'a>(args: &Args<'a>, _: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    Ok(MetaCommand::Exit(args.parse_at(0)?.unwrap_or(0)))
+}
+
+fn headers<'a>(
+    args: &Args<'a>,
+    session: &mut Session,
+) -> Result<MetaCommand, MetaCommandError<'a>> {
+    session.output.headers = args.switch(0)?;
+    Ok(MetaCommand::Done)
+}
+
+fn help<'a>(args: &Args<'a>, _: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    let topic = args.get(0);
+    if let Some(topic) = topic
+        && !META_COMMANDS.iter().any(|c| c.matches(topic))
+    {
+        return Err(MetaCommandError::UnrecognizedCommand {
+            meta: topic.to_string(),
+            input: args.input,
+        });
+    }
+
+    let mut out = std::io::stdout().lock();
+    let width = META_COMMANDS
+        .iter()
+        .map(|c| c.usage.len())
+        .max()
+        .unwrap_or(0);
+    for command in META_COMMANDS {
+        if topic.is_none_or(|topic| command.matches(topic)) {
+            writeln!(out, "{:<width$}  {}", command.usage, command.help)?;
+        }
+    }
+    Ok(MetaCommand::Done)
+}
+
+fn import<'a>(args: &Args<'a>, session: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    args.table(1, session.table.schema())?;
+    let format = match (args.option("--separator"), args.flag("--json")) {
+        (Some(value), true) => return Err(args.invalid(value)),
+        (Some(value), false) => ImportFormat::Csv {
+            separator: parse_separator(value).ok_or(args.invalid(value))?,
+        },
+        (None, false) => ImportFormat::Csv { separator: ',' },
+        (None, true) => ImportFormat::Json,
+    };
+    Ok(MetaCommand::Import {
+        path: args.get(0).unwrap_or_default().to_string(),
+        format,
+    })
+}
+
+fn mode<'a>(args: &Args<'a>, session: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    let name = args.get(0).unwrap_or_default();
+    let mode = Mode::from_name(name).ok_or(args.invalid(name))?;
+    // Statements always target the single table, but accept its name
+    // so `.mode insert users` reads naturally.
+    if mode == Mode::Insert {
+        args.table(1, session.table.schema())?;
+    } else if let Some(extra) = args.get(1) {
+        return Err(args.invalid(extra));
+    }
+
+    session.output.mode = mode;
+    session.output.separator = mode.default_separator().to_string();
+    Ok(MetaCommand::Done)
+}
+
+fn nullvalue<'a>(
+    args: &Args<'a>,
+    session: &mut Session,
+) -> Result<MetaCommand, MetaCommandError<'a>> {
+    session.output.nullvalue = args.get(0).unwrap_or_default().to_string();
+    Ok(MetaCommand::Done)
+}
+
+fn log<'a>(args: &Args<'a>, session: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    session.log = match args.get(0).unwrap_or_default() {
+        "off" => None,
+        path => Some(LogFile::open(path)?),
+    };
+    Ok(MetaCommand::Done)
+}
+
+fn once<'a>(args: &Args<'a>, session: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    let path = args.get(0).unwrap_or_default();
+    session.output.target = Some(OutputFile::create(path, true)?);
+    Ok(MetaCommand::Done)
+}
+
+fn open<'a>(args: &Args<'a>, session: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    let path = args.get(0).unwrap_or_default();
+    let readonly = args.flag("--readonly");
+    // Flushed before anything touches the path, which may be this same file.
+    session.table.flush()?;
+    if args.flag("--new") {
+        if readonly {
+            return Err(args.invalid("--new"));
+        }
+        std::fs::File::create(path)?;
+    }
+
+    let table = if readonly {
+        Table::open_readonly(path)
+    } else {
+        Table::new(path)
+    }?;
+    session.table = table;
+    session.poisoned = false;
+    Ok(MetaCommand::Done)
+}
+
+fn output<'a>(args: &Args<'a>, session: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    session.output.target = match args.get(0) {
+        Some(path) => Some(OutputFile::create(path, false)?),
+        None => None,
+    };
+    Ok(MetaCommand::Done)
+}
+
+fn pager<'a>(args: &Args<'a>, session: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    session.output.pager = args.switch(0)?;
+    Ok(MetaCommand::Done)
+}
+
+fn permissive<'a>(
+    args: &Args<'a>,
+    session: &mut Session,
+) -> Result<MetaCommand, MetaCommandError<'a>> {
+    session.prepare.strict = !args.switch(0)?;
+    Ok(MetaCommand::Done)
+}
+
+fn read<'a>(args: &Args<'a>, _: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    Ok(MetaCommand::Read(
+        args.get(0).unwrap_or_default().to_string(),
+    ))
+}
+
+fn schema<'a>(args: &Args<'a>, session: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    let schema = session.table.schema();
+    args.table(0, schema)?;
+    println!("{}", schema.create_sql());
+    Ok(MetaCommand::Done)
+}
+
+fn separator<'a>(
+    args: &Args<'a>,
+    session: &mut Session,
+) -> Result<MetaCommand, MetaCommandError<'a>> {
+    session.output.separator = args.get(0).unwrap_or_default().replace("\\t", "\t");
+    Ok(MetaCommand::Done)
+}
+
+fn show<'a>(_: &Args<'a>, session: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    session.show(&mut std::io::stdout().lock())?;
+    Ok(MetaCommand::Done)
+}
+
+/// Shows the pragma when given only its name (or nothing), sets it when
+/// given `name=value`.
+fn pragma<'a>(args: &Args<'a>, session: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    let arg = args.get(0).unwrap_or("skip_corrupt");
+    let (name, value) = arg
+        .split_once('=')
+        .map_or((arg, None), |(n, v)| (n, Some(v)));
+    if name != "skip_corrupt" {
+        return Err(args.invalid(name));
+    }
+    match value {
+        None => {
+            let value = if session.skip_corrupt { "on" } else { "off" };
+            writeln!(std::io::stdout().lock(), "skip_corrupt={value}")?;
+        }
+        Some("on") => session.skip_corrupt = true,
+        Some("off") => session.skip_corrupt = false,
+        Some(value) => return Err(args.invalid(value)),
+    }
+    Ok(MetaCommand::Done)
+}
+
+fn tablesize<'a>(
+    args: &Args<'a>,
+    session: &mut Session,
+) -> Result<MetaCommand, MetaCommandError<'a>> {
+    let table = &session.table;
+    args.table(0, table.schema())?;
+    let row_bytes = table.num_rows * table.schema().row_size();
+    println!("{:<18}{}", "table:", table.schema().name);
+    println!("{:<18}{}", "row count:", table.num_rows);
+    println!("{:<18}{}", "page count:", table.num_pages());
+    println!("{:<18}{row_bytes} bytes", "row bytes:");
+    println!(
+        "{:<18}{} bytes",
+        "page bytes:",
+        table.num_pages() * PAGE_SIZE
+    );
+    Ok(MetaCommand::Done)
+}
+
+fn timeout_ms<'a>(
+    args: &Args<'a>,
+    session: &mut Session,
+) -> Result<MetaCommand, MetaCommandError<'a>> {
+    let ms = args.parse_at::<u64>(0)?.unwrap_or_default();
+    session.timeout = (ms > 0).then(|| std::time::Duration::from_millis(ms));
+    Ok(MetaCommand::Done)
+}
+
+fn timer<'a>(args: &Args<'a>, session: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    session.timer = args.switch(0)?;
+    Ok(MetaCommand::Done)
+}
+
+fn width<'a>(args: &Args<'a>, session: &mut Session) -> Result<MetaCommand, MetaCommandError<'a>> {
+    session.output.widths = (0..args.positional.len())
+        .map(|i| match args.parse_at(i)?.unwrap_or_default() {
+            width if width > MAX_WIDTH => Err(args.invalid(args.get(i).unwrap_or_default())),
+            width => Ok(width),
+        })
+        .collect::<Result<_, _>>()?;
+    Ok(MetaCommand::Done)
+}
+
+/// Accepts a single character, or `\t` for a tab.
+fn parse_separator(value: &str) -> Option<char> {
+    if value == "\\t" {
+        return Some('\t');
+    }
+    let mut chars = value.chars();
+    let separator = chars.next()?;
+    chars.next().is_none().then_some(separator)
+}
+
+/// Writes the table as a script that can be piped back into rlite.
+///
+/// The table itself is implicit, so its schema is emitted as a comment.
+pub fn dump<'r>(
+    schema: &TableSchema,
+    rows: impl Iterator<Item = RowRef<'r>>,
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    writeln!(out, "-- {}", schema.create_sql())?;
+
+    for row in rows {
+        writeln!(out, "{}", row.insert_statement())?;
+    }
+
+    Ok(())
+}
+
+/// Describes the database file. There is no file header yet: the file is the
+/// table's pages back to back, so everything here comes from the pager.
+pub fn dbinfo(table: &Table, out: &mut impl Write) -> std::io::Result<()> {
+    writeln!(out, "{:<18}{}", "database file:", table.path().display())?;
+    writeln!(out, "{:<18}{} bytes", "file size:", table.file_length())?;
+    writeln!(out, "{:<18}{PAGE_SIZE}", "page size:")?;
+    writeln!(out, "{:<18}{}", "page count:", table.num_pages())?;
+    writeln!(out, "{:<18}{}", "cached pages:", table.cached_pages())?;
+    writeln!(out, "{:<18}{}", "row count:", table.num_rows)?;
+    Ok(())
+}
+
+/// Prints the table's row layout and the page layout.
+pub fn constants(schema: &TableSchema, out: &mut impl Write) -> std::io::Result<()> {
+    let mut constants = vec![("ROW_SIZE".to_string(), schema.row_size())];
+    for column in &schema.columns {
+        let name = column.name.to_uppercase();
+        constants.push((format!("{name}_OFFSET"), column.offset));
+        constants.push((format!("{name}_SIZE"), column.size()));
+    }
+    constants.extend([
+        ("PAGE_SIZE".to_string(), PAGE_SIZE),
+        ("ROWS_PER_PAGE".to_string(), schema.rows_per_page()),
+        ("TABLE_MAX_PAGES".to_string(), TABLE_MAX_PAGES),
+        ("TABLE_MAX_ROWS".to_string(), schema.max_rows()),
+    ]);
+    for (name, value) in constants {
+        writeln!(out, "{:<18}{value}", format!("{name}:"))?;
+    }
+    Ok(())
+}
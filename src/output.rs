@@ -0,0 +1,333 @@
+use crate::schema::{ColumnType, TableSchema};
+use crate::tokenizer::RowRef;
+use std::borrow::Cow;
+use std::io::Write;
+
+/// The widest `.width` accepted. The longest value, an email, is 255 bytes,
+/// so anything wider is only padding.
+pub const MAX_WIDTH: usize = 1000;
+
+#[derive(Clone, Copy, PartialEq, Eq)]
+pub enum Mode {
+    /// The original `(id username email)` format.
+    Tuple,
+    /// Values joined by the separator, without quoting.
+    List,
+    Csv,
+    Json,
+    /// Aligned columns under an underlined header.
+    Column,
+    /// Aligned columns inside a bordered box.
+    Table,
+    /// `insert` statements that can be replayed into another database.
+    Insert,
+}
+
+impl Mode {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "tuple" => Some(Mode::Tuple),
+            "list" => Some(Mode::List),
+            "csv" => Some(Mode::Csv),
+            "json" => Some(Mode::Json),
+            "column" => Some(Mode::Column),
+            "table" => Some(Mode::Table),
+            "insert" => Some(Mode::Insert),
+            _ => None,
+        }
+    }
+
+    /// The separator a mode starts out with when selected.
+    pub fn default_separator(&self) -> &'static str {
+        match self {
+            Mode::List => "|",
+            _ => ",",
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Mode::Tuple => "tuple",
+            Mode::List => "list",
+            Mode::Csv => "csv",
+            Mode::Json => "json",
+            Mode::Column => "column",
+            Mode::Table => "table",
+            Mode::Insert => "insert",
+        }
+    }
+}
+
+/// A file that query results are redirected to.
+pub struct OutputFile {
+    pub path: String,
+    pub file: std::io::BufWriter<std::fs::File>,
+    /// Return to stdout after the next query.
+    pub once: bool,
+}
+
+impl OutputFile {
+    pub fn create(path: &str, once: bool) -> std::io::Result<Self> {
+        Ok(Self {
+            path: path.to_string(),
+            file: std::io::BufWriter::new(std::fs::File::create(path)?),
+            once,
+        })
+    }
+}
+
+/// How `select` results are rendered, adjustable with meta commands.
+pub struct OutputSettings {
+    pub mode: Mode,
+    pub headers: bool,
+    pub separator: String,
+    /// Printed in place of NULL values. Every column is currently NOT NULL.
+    pub nullvalue: String,
+    /// Fixed column widths for column/table modes; 0 or missing means automatic.
+    pub widths: Vec<usize>,
+    /// Where results go; `None` means stdout.
+    pub target: Option<OutputFile>,
+    /// Send results shown on a terminal through `$PAGER`.
+    pub pager: bool,
+}
+
+impl Default for OutputSettings {
+    fn default() -> Self {
+        Self {
+            mode: Mode::Tuple,
+            headers: false,
+            separator: ",".to_string(),
+            nullvalue: String::new(),
+            widths: Vec::new(),
+            target: None,
+            pager: true,
+        }
+    }
+}
+
+impl OutputSettings {
+    /// Writes rows to the current target, dropping a `.once` target afterwards.
+    pub fn emit<'a>(
+        &mut self,
+        schema: &TableSchema,
+        rows: impl Iterator<Item = RowRef<'a>>,
+    ) -> std::io::Result<()> {
+        let Some(mut target) = self.target.take() else {
+            if let Some(mut pager) = self.spawn_pager() {
+                let result = self.write_rows(schema, rows, &mut pager.stdin.take().unwrap());
+                pager.wait()?;
+                // Quitting the pager early closes the pipe; that is not an error.
+                return match result {
+                    Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(()),
+                    result => result,
+                };
+            }
+            return self.write_rows(schema, rows, &mut std::io::stdout().lock());
+        };
+
+        let result = self
+            .write_rows(schema, rows, &mut target.file)
+            .and_then(|()| target.file.flush());
+        if !target.once {
+            self.target = Some(target);
+        }
+        result
+    }
+
+    /// Starts `$PAGER`, or `less -FRX` if unset, when results would go to a
+    /// terminal. Falls back to plain stdout if the pager cannot be started.
+    fn spawn_pager(&self) -> Option<std::process::Child> {
+        use std::io::IsTerminal;
+        if !self.pager || !std::io::stdout().is_terminal() {
+            return None;
+        }
+
+        let command = std::env::var("PAGER").unwrap_or_else(|_| "less -FRX".to_string());
+        let mut words = command.split_whitespace();
+        std::process::Command::new(words.next()?)
+            .args(words)
+            .stdin(std::process::Stdio::piped())
+            .spawn()
+            .ok()
+    }
+
+    pub fn write_rows<'a>(
+        &self,
+        schema: &TableSchema,
+        rows: impl Iterator<Item = RowRef<'a>>,
+        out: &mut impl Write,
+    ) -> std::io::Result<()> {
+        let header = schema
+            .column_names()
+            .into_iter()
+            .map(Cow::from)
+            .collect::<Vec<_>>();
+        match self.mode {
+            Mode::Tuple => {
+                for row in rows {
+                    row.print_to(out)?;
+                }
+            }
+            Mode::List => {
+                if self.headers {
+                    writeln!(out, "{}", header.join(&self.separator))?;
+                }
+                for row in rows {
+                    writeln!(out, "{}", row.values().join(&self.separator))?;
+                }
+            }
+            Mode::Csv => {
+                if self.headers {
+                    self.write_csv_record(&header, out)?;
+                }
+                for row in rows {
+                    self.write_csv_record(&row.values(), out)?;
+                }
+            }
+            Mode::Json => {
+                let mut empty = true;
+                write!(out, "[")?;
+                for row in rows {
+                    write!(out, "{}", if empty { "\n" } else { ",\n" })?;
+                    write_json_object(schema, &row, out)?;
+                    empty = false;
+                }
+                writeln!(out, "{}]", if empty { "" } else { "\n" })?;
+            }
+            Mode::Insert => {
+                for row in rows {
+                    writeln!(out, "{}", row.insert_statement())?;
+                }
+            }
+            Mode::Column | Mode::Table => {
+                let rows = rows.map(|row| row.values()).collect::<Vec<_>>();
+                self.write_aligned(&header, &rows, out)?;
+            }
+        }
+
+        Ok(())
+    }
+
+    fn write_aligned(
+        &self,
+        header: &[Cow<'_, str>],
+        rows: &[Vec<Cow<'_, str>>],
+        out: &mut impl Write,
+    ) -> std::io::Result<()> {
+        let widths = (0..header.len())
+            .map(|i| match self.widths.get(i) {
+                Some(&width) if width > 0 => width,
+                _ => std::iter::once(header)
+                    .chain(rows.iter().map(Vec::as_slice))
+                    .map(|row| row[i].chars().count())
+                    .max()
+                    .unwrap_or_default(),
+            })
+            .collect::<Vec<_>>();
+
+        let boxed = self.mode == Mode::Table;
+        let border = widths
+            .iter()
+            .map(|&w| "-".repeat(w + 2))
+            .collect::<Vec<_>>()
+            .join("+");
+        let write_line = |out: &mut dyn Write, values: &[Cow<'_, str>]| {
+            let cells = values
+                .iter()
+                .zip(&widths)
+                .map(|(value, &width)| {
+                    let value = value.chars().take(width).collect::<String>();
+                    format!("{value:<width$}")
+                })
+                .collect::<Vec<_>>();
+            if boxed {
+                writeln!(out, "| {} |", cells.join(" | "))
+            } else {
+                writeln!(out, "{}", cells.join("  ").trim_end())
+            }
+        };
+
+        if boxed {
+            writeln!(out, "+{border}+")?;
+        }
+        write_line(out, header)?;
+        if boxed {
+            writeln!(out, "+{border}+")?;
+        } else {
+            let underline = widths.iter().map(|&w| "-".repeat(w)).collect::<Vec<_>>();
+            writeln!(out, "{}", underline.join("  "))?;
+        }
+        for row in rows {
+            write_line(out, row)?;
+        }
+        if boxed {
+            writeln!(out, "+{border}+")?;
+        }
+
+        Ok(())
+    }
+
+    fn write_csv_record(
+        &self,
+        values: &[Cow<'_, str>],
+        out: &mut impl Write,
+    ) -> std::io::Result<()> {
+        for (i, value) in values.iter().enumerate() {
+            if i > 0 {
+                write!(out, "{}", self.separator)?;
+            }
+            write!(out, "{}", csv_quote(value, &self.separator))?;
+        }
+        writeln!(out)
+    }
+}
+
+fn write_json_object(
+    schema: &TableSchema,
+    row: &RowRef<'_>,
+    out: &mut impl Write,
+) -> std::io::Result<()> {
+    write!(out, "{{")?;
+    for (i, (column, value)) in schema.columns.iter().zip(row.values()).enumerate() {
+        let separator = if i > 0 { "," } else { "" };
+        match column.ctype {
+            ColumnType::Integer => write!(out, "{separator}\"{}\":{value}", column.name)?,
+            ColumnType::Varchar(_) => write!(
+                out,
+                "{separator}\"{}\":{}",
+                column.name,
+                json_string(&value)
+            )?,
+        }
+    }
+    write!(out, "}}")
+}
+
+pub fn json_string(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len() + 2);
+    escaped.push('"');
+    for c in value.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            c if c.is_control() => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped.push('"');
+    escaped
+}
+
+/// Quotes a field if it contains the separator, a quote, or a line break.
+pub fn csv_quote<'a>(value: &'a str, separator: &str) -> Cow<'a, str> {
+    let needs_quotes =
+        (!separator.is_empty() && value.contains(separator)) || value.contains(['"', '\n', '\r']);
+    if needs_quotes {
+        format!("\"{}\"", value.replace('"', "\"\"")).into()
+    } else {
+        value.into()
+    }
+}
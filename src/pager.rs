@@ -1,8 +1,14 @@
-use crate::constants::{PAGE_SIZE, ROW_SIZE, ROWS_PER_PAGE, TABLE_MAX_PAGES};
+use crate::constants::{PAGE_SIZE, TABLE_MAX_PAGES};
+use crate::error_class::ErrorClass;
+use crate::progress::Progress;
+use crate::schema::TableSchema;
+use crate::tokenizer::{Row, RowRef};
 use std::{
     fs::{self, File},
-    io::{Read, Seek, SeekFrom, Write},
-    path::Path,
+    io::{IoSlice, Read, Seek, SeekFrom, Write},
+    ops::{Bound, RangeBounds},
+    path::{Path, PathBuf},
+    rc::Rc,
 };
 
 #[derive(Debug)]
@@ -10,6 +16,8 @@ pub enum PageError {
     Io(std::io::Error),
     TryFromIntError(std::num::TryFromIntError),
     FetchOutOfBounds(usize),
+    /// The file holds more pages than a table can address.
+    TooManyPages(u64),
 }
 
 impl From<std::io::Error> for PageError {
@@ -24,6 +32,17 @@ impl From<std::num::TryFromIntError> for PageError {
     }
 }
 
+impl PageError {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            Self::Io(_) => ErrorClass::Io,
+            Self::TryFromIntError(_) | Self::FetchOutOfBounds(_) | Self::TooManyPages(_) => {
+                ErrorClass::Corruption
+            }
+        }
+    }
+}
+
 impl std::fmt::Display for PageError {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -31,51 +50,72 @@ impl std::fmt::Display for PageError {
             Self::FetchOutOfBounds(i) => {
                 write!(
                     f,
-                    "Tried to fetch page out of bounds. {i} > {TABLE_MAX_PAGES}"
+                    "Tried to fetch page out of bounds. {i} >= {TABLE_MAX_PAGES}"
                 )
             }
+            Self::TooManyPages(pages) => write!(
+                f,
+                "File has {pages} pages, more than the maximum of {TABLE_MAX_PAGES}"
+            ),
             Self::TryFromIntError(err) => write!(f, "TryFromIntError: {err}"),
         }
     }
 }
 
+/// File offsets are `u64` whatever the pointer width, so a file too large to
+/// address in memory is still measured correctly and rejected.
+const PAGE_BYTES: u64 = PAGE_SIZE as u64;
+
+/// Where a page starts in the file.
+fn page_offset(page_num: usize) -> Result<u64, PageError> {
+    u64::try_from(page_num)?
+        .checked_mul(PAGE_BYTES)
+        .ok_or(PageError::FetchOutOfBounds(page_num))
+}
+
 type Page = Option<Box<[u8; PAGE_SIZE]>>;
 pub struct Pager {
+    path: PathBuf,
     file: File,
-    file_length: usize,
+    file_length: u64,
+    /// Where the file cursor is, so reads and writes that continue where the
+    /// last one ended don't seek.
+    position: u64,
     pages: Box<[Page; TABLE_MAX_PAGES]>,
 }
 
 impl Pager {
-    fn new(filename: impl AsRef<Path>) -> Result<Self, PageError> {
+    fn new(filename: impl AsRef<Path>, readonly: bool) -> Result<Self, PageError> {
         let mut file = fs::OpenOptions::new()
             .read(true)
-            .write(true)
-            .create(true)
+            .write(!readonly)
+            .create(!readonly)
             .truncate(false)
-            .open(filename)?;
-        let file_length = usize::try_from(file.seek(SeekFrom::End(0))?)?;
+            .open(&filename)?;
+        let file_length = file.seek(SeekFrom::End(0))?;
 
         Ok(Self {
+            path: filename.as_ref().to_path_buf(),
             file,
             file_length,
+            position: file_length,
             pages: Box::new(std::array::from_fn(|_| None)),
         })
     }
 
     fn get_page(&mut self, page_num: usize) -> Result<&mut [u8], PageError> {
-        if page_num > TABLE_MAX_PAGES {
+        if page_num >= TABLE_MAX_PAGES {
             return Err(PageError::FetchOutOfBounds(page_num));
         }
 
         if self.pages[page_num].is_none() {
             let mut page = Box::new([0u8; PAGE_SIZE]);
-            let num_pages = self.file_length.div_ceil(PAGE_SIZE);
+            let offset = page_offset(page_num)?;
 
-            if page_num <= num_pages {
-                self.file
-                    .seek(SeekFrom::Start((page_num * PAGE_SIZE) as u64))?;
-                let _ = self.file.read(&mut *page)?;
+            if offset <= self.file_length {
+                self.seek_to(offset)?;
+                let read = self.file.read(&mut *page)?;
+                self.position += read as u64;
             }
             self.pages[page_num] = Some(page);
         }
@@ -87,112 +127,379 @@ impl Pager {
             .unwrap())
     }
 
-    fn flush(&mut self, page_num: usize, size: usize) {
-        if let Some(page) = &self.pages[page_num] {
-            self.file
-                .seek(SeekFrom::Start((page_num * PAGE_SIZE) as u64))
-                .expect("Unable to seek in flush");
-            self.file
-                .write_all(&page[..size])
-                .expect("Unable to write in flush");
+    fn seek_to(&mut self, offset: u64) -> Result<(), PageError> {
+        if self.position != offset {
+            self.position = self.file.seek(SeekFrom::Start(offset))?;
         }
+        Ok(())
+    }
+
+    /// Writes a run of adjacent cached pages, starting at `first_page`, with a
+    /// single vectored write. `sizes` holds how many bytes of each to write.
+    fn write_pages(&mut self, first_page: usize, sizes: &[usize]) -> Result<(), PageError> {
+        let offset = page_offset(first_page)?;
+        self.seek_to(offset)?;
+
+        let mut slices = sizes
+            .iter()
+            .zip(&self.pages[first_page..])
+            .filter_map(|(&size, page)| page.as_ref().map(|page| IoSlice::new(&page[..size])))
+            .collect::<Vec<_>>();
+        let mut remaining = &mut slices[..];
+        while !remaining.is_empty() {
+            let written = match self.file.write_vectored(remaining) {
+                Ok(0) => return Err(std::io::Error::from(std::io::ErrorKind::WriteZero).into()),
+                Ok(written) => written,
+                Err(e) if e.kind() == std::io::ErrorKind::Interrupted => continue,
+                Err(e) => return Err(e.into()),
+            };
+            self.position += written as u64;
+            IoSlice::advance_slices(&mut remaining, written);
+        }
+
+        let end = offset + u64::try_from(sizes.iter().sum::<usize>())?;
+        self.file_length = self.file_length.max(end);
+        Ok(())
     }
 }
 
 pub struct Table {
     pub num_rows: usize,
+    /// Opened without write access; nothing is ever flushed.
+    pub readonly: bool,
+    /// Shared so callers can hold on to it while they borrow the rows.
+    schema: Rc<TableSchema>,
     pager: Pager,
 }
 
 impl Table {
     pub fn new(filename: impl AsRef<Path>) -> Result<Self, PageError> {
-        let pager = Pager::new(filename)?;
-        let num_rows = pager.file_length / ROW_SIZE;
+        Self::open(filename, false)
+    }
 
-        Ok(Self { num_rows, pager })
+    /// Opens an existing database file without write access.
+    pub fn open_readonly(filename: impl AsRef<Path>) -> Result<Self, PageError> {
+        Self::open(filename, true)
     }
 
-    // Will panic for out of bounds
-    pub fn row_slot(&mut self, row_num: usize) -> &mut [u8] {
-        let page_num = row_num / ROWS_PER_PAGE;
-        let page = self.pager.get_page(page_num).unwrap();
+    fn open(filename: impl AsRef<Path>, readonly: bool) -> Result<Self, PageError> {
+        let pager = Pager::new(filename, readonly)?;
+        let num_pages = pager.file_length.div_ceil(PAGE_BYTES);
+        if num_pages > u64::try_from(TABLE_MAX_PAGES)? {
+            return Err(PageError::TooManyPages(num_pages));
+        }
+        // Small enough to fit in memory now that the page count is checked.
+        let file_length = usize::try_from(pager.file_length)?;
+        let schema = TableSchema::users();
+        // Full pages are written whole, padding included; only the last page
+        // is cut off after its last row.
+        let num_rows = file_length / PAGE_SIZE * schema.rows_per_page()
+            + file_length % PAGE_SIZE / schema.row_size();
 
-        let row_offset = row_num % ROWS_PER_PAGE;
-        let byte_offset = row_offset * ROW_SIZE;
+        Ok(Self {
+            num_rows,
+            readonly,
+            schema: Rc::new(schema),
+            pager,
+        })
+    }
 
-        &mut page[byte_offset..byte_offset + ROW_SIZE]
+    pub fn schema(&self) -> &Rc<TableSchema> {
+        &self.schema
     }
-}
 
-impl Drop for Table {
-    fn drop(&mut self) {
-        let pager = &mut self.pager;
-        let num_full_pages = self.num_rows / ROWS_PER_PAGE;
+    pub fn path(&self) -> &Path {
+        &self.pager.path
+    }
 
-        for i in 0..num_full_pages {
-            if pager.pages[i].is_none() {
-                continue;
-            }
-            pager.flush(i, PAGE_SIZE);
-            pager.pages[i] = None;
+    pub fn file_length(&self) -> u64 {
+        self.pager.file_length
+    }
+
+    /// Number of pages holding rows.
+    pub fn num_pages(&self) -> usize {
+        self.num_rows.div_ceil(self.schema.rows_per_page())
+    }
+
+    pub fn cached_pages(&self) -> usize {
+        self.pager
+            .pages
+            .iter()
+            .filter(|page| page.is_some())
+            .count()
+    }
+
+    /// Writes every cached page to disk and empties the cache.
+    pub fn flush(&mut self) -> Result<(), PageError> {
+        let pager = &mut self.pager;
+        if self.readonly {
+            pager.pages.iter_mut().for_each(|page| *page = None);
+            return Ok(());
         }
 
-        // Partial pages
-        let num_aditional_rows = self.num_rows % ROWS_PER_PAGE;
-        if num_aditional_rows > 0 {
-            let page_num = num_full_pages;
-            if pager.pages[page_num].is_some() {
-                pager.flush(page_num, num_aditional_rows * ROW_SIZE);
-                pager.pages[page_num] = None;
-            }
+        // Full pages, then the partial last page, skipping pages not in cache.
+        let rows_per_page = self.schema.rows_per_page();
+        let num_full_pages = self.num_rows / rows_per_page;
+        let num_aditional_rows = self.num_rows % rows_per_page;
+        let cached = (0..num_full_pages)
+            .map(|i| (i, PAGE_SIZE))
+            .chain(
+                (num_aditional_rows > 0)
+                    .then_some((num_full_pages, num_aditional_rows * self.schema.row_size())),
+            )
+            .filter(|&(i, _)| pager.pages[i].is_some())
+            .collect::<Vec<_>>();
+
+        for run in cached.chunk_by(|a, b| b.0 == a.0 + 1) {
+            let sizes = run.iter().map(|&(_, size)| size).collect::<Vec<_>>();
+            pager.write_pages(run[0].0, &sizes)?;
         }
 
         for page in pager.pages.iter_mut() {
             *page = None;
         }
+        pager.file.sync_data()?;
+
+        Ok(())
     }
-}
 
-pub struct Cursor<'a> {
-    table: &'a mut Table,
-    row_num: usize,
-    pub end_of_table: bool,
-}
+    /// A cursor over every row, in order.
+    pub fn rows(&mut self) -> Result<Cursor<'_>, PageError> {
+        self.range(..)
+    }
 
-impl<'a> Cursor<'a> {
-    pub fn from_start(table: &'a mut Table) -> Self {
-        let row_num = table.num_rows;
-        Self {
-            table,
-            row_num: 0,
-            end_of_table: row_num == 0,
+    /// A cursor over the rows at the given positions, in order; positions past
+    /// the last row are left out. Only the pages holding those rows are
+    /// loaded, all up front, so the cursor reads straight out of the cache.
+    pub fn range(&mut self, rows: impl RangeBounds<usize>) -> Result<Cursor<'_>, PageError> {
+        let start = match rows.start_bound() {
+            Bound::Included(&start) => start,
+            Bound::Excluded(&start) => start.saturating_add(1),
+            Bound::Unbounded => 0,
         }
+        .min(self.num_rows);
+        let end = match rows.end_bound() {
+            Bound::Included(&end) => end.saturating_add(1),
+            Bound::Excluded(&end) => end,
+            Bound::Unbounded => self.num_rows,
+        }
+        .clamp(start, self.num_rows);
+
+        let rows_per_page = self.schema.rows_per_page();
+        for page_num in start / rows_per_page..end.div_ceil(rows_per_page) {
+            self.pager.get_page(page_num)?;
+        }
+
+        Ok(Cursor {
+            table: self,
+            row_num: start,
+            end,
+        })
     }
 
-    pub fn from_end(table: &'a mut Table) -> Self {
-        let row_num = table.num_rows;
-        Self {
-            table,
-            row_num,
-            end_of_table: true,
+    /// Appends rows in one pass, filling each page before moving to the next,
+    /// and flushes once at the end. Returns how many rows were inserted.
+    ///
+    /// Callers check that the rows fit; running out of pages stops with
+    /// `FetchOutOfBounds` after the rows that did fit.
+    pub fn insert_many<'r>(
+        &mut self,
+        rows: impl IntoIterator<Item = &'r Row>,
+    ) -> Result<usize, PageError> {
+        let mut rows = rows.into_iter().peekable();
+        let mut inserted = 0;
+        let rows_per_page = self.schema.rows_per_page();
+        let row_size = self.schema.row_size();
+        while rows.peek().is_some() {
+            let page = self.pager.get_page(self.num_rows / rows_per_page)?;
+            for slot in self.num_rows % rows_per_page..rows_per_page {
+                let Some(row) = rows.next() else {
+                    break;
+                };
+                page[slot * row_size..(slot + 1) * row_size].copy_from_slice(row.serialize());
+                self.num_rows += 1;
+                inserted += 1;
+            }
         }
+        self.flush()?;
+
+        Ok(inserted)
     }
 
-    pub fn value(&mut self) -> &mut [u8] {
-        let row_num = self.row_num;
-        let page_num = row_num / ROWS_PER_PAGE;
-        let page = self.table.pager.get_page(page_num).unwrap();
+    /// Copies every row into a new database file, returning how many were copied.
+    /// The destination must not exist yet.
+    pub fn backup_to(&mut self, filename: impl AsRef<Path>) -> Result<usize, PageError> {
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&filename)?;
+        let mut backup = Table::new(&filename)?;
 
-        let row_offset = row_num % ROWS_PER_PAGE;
-        let byte_offset = row_offset * ROW_SIZE;
+        let mut progress = Progress::new("Copying", self.num_rows);
+        for row in self.rows()? {
+            backup.append_slot().copy_from_slice(row.as_bytes());
+            progress.tick();
+        }
+        drop(progress);
+        backup.flush()?;
 
-        &mut page[byte_offset..byte_offset + ROW_SIZE]
+        Ok(backup.num_rows)
     }
 
-    pub fn advance(&mut self) {
-        self.row_num += 1;
-        if self.row_num >= self.table.num_rows {
-            self.end_of_table = true;
+    /// Checks a database file's structure without opening it as a table,
+    /// returning a description of each problem found.
+    pub fn check(filename: impl AsRef<Path>) -> Result<Vec<String>, PageError> {
+        let bytes = fs::read(filename)?;
+        let mut problems = Vec::new();
+        let schema = TableSchema::users();
+        let (row_size, rows_per_page) = (schema.row_size(), schema.rows_per_page());
+
+        let num_pages = bytes.len().div_ceil(PAGE_SIZE);
+        if num_pages > TABLE_MAX_PAGES {
+            problems.push(format!(
+                "file has {num_pages} pages, more than the maximum of {TABLE_MAX_PAGES}"
+            ));
         }
+
+        for (page_num, page) in bytes.chunks(PAGE_SIZE).enumerate() {
+            let rows = page.chunks(row_size).take(rows_per_page);
+            for (slot, row) in rows.enumerate() {
+                // Only the last page can be cut short, in the middle of a row.
+                let row = match RowRef::new(row, &schema) {
+                    Ok(row) => row,
+                    Err(_) => {
+                        problems.push(format!(
+                            "file ends with {} bytes of a partial row",
+                            row.len()
+                        ));
+                        continue;
+                    }
+                };
+                let row_num = page_num * rows_per_page + slot;
+                // Every slot in the file is counted in `num_rows`, so none may
+                // be blank, and each must pass the rule `select` reads rows by.
+                if row.is_blank() {
+                    problems.push(format!("row {row_num} on page {page_num} is blank"));
+                } else if row.validate().is_err() {
+                    problems.push(format!(
+                        "row {row_num} on page {page_num} has text that is not padded UTF-8"
+                    ));
+                }
+            }
+            if page.len() == PAGE_SIZE && page[rows_per_page * row_size..].iter().any(|&b| b != 0) {
+                problems.push(format!("page {page_num} has data after its last row"));
+            }
+        }
+
+        Ok(problems)
+    }
+
+    /// Salvages what it can from a damaged database file into a new one.
+    ///
+    /// The file is read page by page, bypassing `num_rows`, and every slot
+    /// that `select` would accept as a row is copied over. Blank slots are
+    /// left behind, and pages without a single row to copy are skipped.
+    pub fn recover(
+        source: impl AsRef<Path>,
+        filename: impl AsRef<Path>,
+    ) -> Result<Recovery, PageError> {
+        let bytes = fs::read(source)?;
+        fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .open(&filename)?;
+        let mut recovered = Table::new(&filename)?;
+        let schema = recovered.schema.clone();
+
+        let mut pages_skipped = 0;
+        for page in bytes.chunks(PAGE_SIZE) {
+            let rows = page
+                .chunks(schema.row_size())
+                .take(schema.rows_per_page())
+                .filter_map(|slot| RowRef::new(slot, &schema).ok())
+                .filter(|row| !row.is_blank() && row.validate().is_ok())
+                .collect::<Vec<_>>();
+            if rows.is_empty() {
+                pages_skipped += 1;
+            }
+            for row in rows {
+                if recovered.num_rows >= schema.max_rows() {
+                    break;
+                }
+                recovered.append_slot().copy_from_slice(row.as_bytes());
+            }
+        }
+        recovered.flush()?;
+
+        Ok(Recovery {
+            rows: recovered.num_rows,
+            pages: bytes.len().div_ceil(PAGE_SIZE),
+            pages_skipped,
+        })
+    }
+
+    /// The slot after the last row, counted in `num_rows` already. Panics if
+    /// the table is full.
+    pub fn append_slot(&mut self) -> &mut [u8] {
+        self.num_rows += 1;
+        self.row_slot(self.num_rows - 1)
+    }
+
+    // Will panic for out of bounds
+    pub fn row_slot(&mut self, row_num: usize) -> &mut [u8] {
+        let (rows_per_page, row_size) = (self.schema.rows_per_page(), self.schema.row_size());
+        let page_num = row_num / rows_per_page;
+        let page = self.pager.get_page(page_num).unwrap();
+
+        let row_offset = row_num % rows_per_page;
+        let byte_offset = row_offset * row_size;
+
+        &mut page[byte_offset..byte_offset + row_size]
+    }
+}
+
+impl Drop for Table {
+    fn drop(&mut self) {
+        if let Err(e) = self.flush() {
+            println!("Unable to flush database: {e}");
+        }
+    }
+}
+
+/// What `Table::recover` managed to salvage.
+pub struct Recovery {
+    pub rows: usize,
+    pub pages: usize,
+    pub pages_skipped: usize,
+}
+
+/// Reads the rows of a `Table::range` in order. The pages are loaded when
+/// the cursor is made, so reading a row cannot fail.
+pub struct Cursor<'a> {
+    table: &'a Table,
+    row_num: usize,
+    end: usize,
+}
+
+impl<'a> Iterator for Cursor<'a> {
+    type Item = RowRef<'a>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.row_num >= self.end {
+            return None;
+        }
+        let schema = &*self.table.schema;
+        let (rows_per_page, row_size) = (schema.rows_per_page(), schema.row_size());
+        let page = self.table.pager.pages[self.row_num / rows_per_page].as_deref()?;
+        let offset = self.row_num % rows_per_page * row_size;
+        self.row_num += 1;
+        RowRef::new(&page[offset..offset + row_size], schema).ok()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let len = self.end - self.row_num;
+        (len, Some(len))
     }
 }
+
+impl ExactSizeIterator for Cursor<'_> {}
@@ -0,0 +1,82 @@
+use std::io::{IsTerminal, Write};
+use std::time::{Duration, Instant};
+
+/// How often a running operation reports on itself.
+const REPORT_INTERVAL: Duration = Duration::from_millis(500);
+
+/// Periodic progress reports on stderr for operations over a known number of
+/// rows, so a slow run can be told apart from a hung one.
+///
+/// Nothing is printed for operations that finish within the first interval.
+pub struct Progress {
+    label: &'static str,
+    total: usize,
+    done: usize,
+    start: Instant,
+    last_report: Instant,
+    /// Rows done as of the last report, if there was one.
+    reported: Option<usize>,
+}
+
+impl Progress {
+    pub fn new(label: &'static str, total: usize) -> Self {
+        let now = Instant::now();
+        Self {
+            label,
+            total,
+            done: 0,
+            start: now,
+            last_report: now,
+            reported: None,
+        }
+    }
+
+    /// Records that another row was processed.
+    pub fn tick(&mut self) {
+        self.done += 1;
+        if self.last_report.elapsed() >= REPORT_INTERVAL {
+            self.last_report = Instant::now();
+            self.report();
+        }
+    }
+
+    fn report(&mut self) {
+        let elapsed = self.start.elapsed().as_secs_f64();
+        let fraction = self.done as f64 / self.total.max(1) as f64;
+        let eta = if self.done > 0 {
+            elapsed / fraction - elapsed
+        } else {
+            0.0
+        };
+        let line = format!(
+            "{}: {}/{} rows ({:.0}%), ETA {eta:.1}s",
+            self.label,
+            self.done,
+            self.total,
+            fraction * 100.0
+        );
+
+        // Redraw a single line on a terminal; log one line per report otherwise.
+        let mut stderr = std::io::stderr().lock();
+        let _ = if stderr.is_terminal() {
+            write!(stderr, "\r{line}\x1b[K")
+        } else {
+            writeln!(stderr, "{line}")
+        };
+        self.reported = Some(self.done);
+    }
+}
+
+impl Drop for Progress {
+    /// Prints a final report and ends the line, if reporting had started.
+    fn drop(&mut self) {
+        if let Some(reported) = self.reported {
+            if reported != self.done {
+                self.report();
+            }
+            if std::io::stderr().is_terminal() {
+                eprintln!();
+            }
+        }
+    }
+}
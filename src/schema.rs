@@ -0,0 +1,128 @@
+//! The layout of a table's rows, described at run time.
+//!
+//! Rows, statement parsing, output, and the layout reports all read the
+//! columns from a `TableSchema` instead of from per-column constants. The
+//! file has no header to record a schema in, so every table is still opened
+//! with `TableSchema::users`.
+
+use crate::constants::{PAGE_SIZE, TABLE_MAX_PAGES};
+use std::ops::Range;
+
+#[derive(Clone, Copy, PartialEq, Eq, Debug)]
+pub enum ColumnType {
+    /// An unsigned 32-bit integer, stored little-endian.
+    Integer,
+    /// UTF-8 text of at most this many bytes, padded with NUL bytes. Sizes
+    /// count bytes, so multibyte characters take up more than one each.
+    Varchar(usize),
+}
+
+impl ColumnType {
+    /// Bytes the column takes up in every row.
+    pub fn size(self) -> usize {
+        match self {
+            ColumnType::Integer => std::mem::size_of::<u32>(),
+            ColumnType::Varchar(max) => max,
+        }
+    }
+}
+
+impl std::fmt::Display for ColumnType {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ColumnType::Integer => write!(f, "INTEGER"),
+            ColumnType::Varchar(max) => write!(f, "VARCHAR({max})"),
+        }
+    }
+}
+
+pub struct Column {
+    pub name: String,
+    pub ctype: ColumnType,
+    /// Where the column starts within a row.
+    pub offset: usize,
+}
+
+impl Column {
+    pub fn size(&self) -> usize {
+        self.ctype.size()
+    }
+
+    /// The column's bytes within a row.
+    pub fn range(&self) -> Range<usize> {
+        self.offset..self.offset + self.size()
+    }
+}
+
+pub struct TableSchema {
+    pub name: String,
+    pub columns: Vec<Column>,
+    row_size: usize,
+}
+
+impl TableSchema {
+    /// Lays the columns out back to back, in the order given.
+    pub fn new(name: &str, columns: &[(&str, ColumnType)]) -> Self {
+        let mut offset = 0;
+        let columns = columns
+            .iter()
+            .map(|&(name, ctype)| {
+                let column = Column {
+                    name: name.to_string(),
+                    ctype,
+                    offset,
+                };
+                offset += ctype.size();
+                column
+            })
+            .collect();
+
+        Self {
+            name: name.to_string(),
+            columns,
+            row_size: offset,
+        }
+    }
+
+    /// The `users` table every database file holds.
+    pub fn users() -> Self {
+        Self::new(
+            "users",
+            &[
+                ("id", ColumnType::Integer),
+                ("username", ColumnType::Varchar(32)),
+                ("email", ColumnType::Varchar(255)),
+            ],
+        )
+    }
+
+    pub fn row_size(&self) -> usize {
+        self.row_size
+    }
+
+    pub fn rows_per_page(&self) -> usize {
+        PAGE_SIZE / self.row_size
+    }
+
+    pub fn max_rows(&self) -> usize {
+        self.rows_per_page() * TABLE_MAX_PAGES
+    }
+
+    pub fn column_names(&self) -> Vec<&str> {
+        self.columns
+            .iter()
+            .map(|column| column.name.as_str())
+            .collect()
+    }
+
+    /// Reconstructs the CREATE TABLE statement for this layout.
+    pub fn create_sql(&self) -> String {
+        let columns = self
+            .columns
+            .iter()
+            .map(|column| format!("{} {}", column.name, column.ctype))
+            .collect::<Vec<_>>()
+            .join(", ");
+        format!("CREATE TABLE {} ({columns});", self.name)
+    }
+}
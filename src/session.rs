@@ -0,0 +1,158 @@
+use crate::output::OutputSettings;
+use crate::pager::Table;
+use crate::tokenizer::PrepareOptions;
+use std::io::Write;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+/// An audit trail of the commands run in a session, opened with `.log`.
+///
+/// Each entry is a `--` comment describing the outcome followed by the
+/// command itself, so the log can be replayed with `.read`.
+pub struct LogFile {
+    pub path: String,
+    file: std::io::BufWriter<std::fs::File>,
+}
+
+impl LogFile {
+    /// Opens a log for appending, creating it if needed.
+    pub fn open(path: &str) -> std::io::Result<Self> {
+        let file = std::fs::OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(path)?;
+        Ok(Self {
+            path: path.to_string(),
+            file: std::io::BufWriter::new(file),
+        })
+    }
+
+    /// Appends one command. `outcome` is either the number of rows the
+    /// command affected, if it has such a number, or its error message.
+    pub fn record(
+        &mut self,
+        input: &str,
+        outcome: Result<Option<usize>, String>,
+        elapsed: Duration,
+    ) -> std::io::Result<()> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .unwrap_or_default()
+            .as_secs();
+        let elapsed = elapsed.as_secs_f64();
+        let outcome = match outcome {
+            Ok(None) => "ok".to_string(),
+            Ok(Some(1)) => "ok, 1 row".to_string(),
+            Ok(Some(rows)) => format!("ok, {rows} rows"),
+            Err(e) => format!("error: {}", e.lines().collect::<Vec<_>>().join(" ")),
+        };
+        let terminator = if input.starts_with('.') { "" } else { ";" };
+
+        writeln!(self.file, "-- {timestamp} {elapsed:.6}s {outcome}")?;
+        writeln!(self.file, "{input}{terminator}")?;
+        self.file.flush()
+    }
+}
+
+/// State that lives for the whole REPL session.
+pub struct Session {
+    pub table: Table,
+    pub output: OutputSettings,
+    pub prepare: PrepareOptions,
+    /// Print the elapsed time after each statement.
+    pub timer: bool,
+    /// Stop scripts and piped input at the first failing command.
+    pub bail: bool,
+    /// Print each command before running it.
+    pub echo: bool,
+    /// Report errors as JSON on stderr instead of text on stdout.
+    pub json_errors: bool,
+    /// How long a statement may run before it is stopped.
+    pub timeout: Option<Duration>,
+    /// Where `.log` records each command, if anywhere.
+    pub log: Option<LogFile>,
+    /// Leave pages holding a damaged row out of scans instead of stopping at
+    /// the row; set with `.pragma skip_corrupt=on`.
+    pub skip_corrupt: bool,
+    /// A command panicked, so the table may be half-updated. Writes are
+    /// refused from then on, but what was inserted before is still flushed.
+    pub poisoned: bool,
+    /// The scripts being read, outermost first, so `.read` can refuse to
+    /// read one inside itself.
+    pub scripts: Vec<std::path::PathBuf>,
+}
+
+impl Session {
+    pub fn new(table: Table, output: OutputSettings) -> Self {
+        Self {
+            table,
+            output,
+            prepare: PrepareOptions::default(),
+            timer: false,
+            bail: false,
+            echo: false,
+            json_errors: false,
+            timeout: None,
+            log: None,
+            skip_corrupt: false,
+            poisoned: false,
+            scripts: Vec::new(),
+        }
+    }
+
+    /// Prints the current settings, one per line, for `.show`.
+    pub fn show(&self, out: &mut impl Write) -> std::io::Result<()> {
+        let output = &self.output;
+        let widths = output
+            .widths
+            .iter()
+            .map(usize::to_string)
+            .collect::<Vec<_>>()
+            .join(" ");
+        let target = output
+            .target
+            .as_ref()
+            .map_or("stdout", |target| target.path.as_str());
+
+        writeln!(out, "{:>10}: {}", "bail", on_off(self.bail))?;
+        writeln!(out, "{:>10}: {}", "filename", self.table.path().display())?;
+        writeln!(out, "{:>10}: {}", "readonly", on_off(self.table.readonly))?;
+        writeln!(out, "{:>10}: {}", "echo", on_off(self.echo))?;
+        writeln!(out, "{:>10}: {}", "mode", output.mode.name())?;
+        writeln!(out, "{:>10}: {}", "headers", on_off(output.headers))?;
+        writeln!(out, "{:>10}: {:?}", "separator", output.separator)?;
+        writeln!(out, "{:>10}: {:?}", "nullvalue", output.nullvalue)?;
+        writeln!(out, "{:>10}: {widths}", "width")?;
+        writeln!(out, "{:>10}: {target}", "output")?;
+        writeln!(out, "{:>10}: {}", "pager", on_off(output.pager))?;
+        writeln!(out, "{:>10}: {}", "timer", on_off(self.timer))?;
+        writeln!(
+            out,
+            "{:>10}: {}",
+            "emailcheck",
+            on_off(self.prepare.check_email)
+        )?;
+        writeln!(
+            out,
+            "{:>10}: {}",
+            "permissive",
+            on_off(!self.prepare.strict)
+        )?;
+        let timeout = self
+            .timeout
+            .map_or("off".to_string(), |t| format!("{} ms", t.as_millis()));
+        writeln!(out, "{:>10}: {timeout}", "timeout")?;
+        let log = self.log.as_ref().map_or("off", |log| log.path.as_str());
+        writeln!(out, "{:>10}: {log}", "log")?;
+        writeln!(
+            out,
+            "{:>10}: skip_corrupt={}",
+            "pragma",
+            on_off(self.skip_corrupt)
+        )?;
+        Ok(())
+    }
+}
+
+fn on_off(value: bool) -> &'static str {
+    if value { "on" } else { "off" }
+}
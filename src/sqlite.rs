@@ -0,0 +1,713 @@
+//! Reading tables out of SQLite 3 database files, and writing one.
+//!
+//! Only what `select` needs is supported: the header, table B-trees with
+//! their overflow pages, and the record format, in files that use UTF-8.
+//! Indexes, `WITHOUT ROWID` tables, and the freelist are never read, and a
+//! write-ahead log next to the file is ignored.
+//!
+//! `export` writes the smallest file SQLite accepts: a header, the schema on
+//! page 1, and one table B-tree, with no freelist and no overflow pages.
+
+use crate::error_class::ErrorClass;
+use crate::pager::PageError;
+use crate::schema::{ColumnType, TableSchema};
+use crate::tokenizer::{DeserializeError, RowRef};
+use std::collections::HashSet;
+use std::io::Write;
+
+const MAGIC: &[u8; 16] = b"SQLite format 3\0";
+const HEADER_SIZE: usize = 100;
+
+pub enum SqliteError {
+    Io(std::io::Error),
+    NotSqlite,
+    Unsupported(String),
+    Corrupt(String),
+    NoSuchTable(String),
+    Page(PageError),
+    CorruptRow(usize, DeserializeError),
+}
+
+impl From<PageError> for SqliteError {
+    fn from(value: PageError) -> Self {
+        Self::Page(value)
+    }
+}
+
+impl From<std::io::Error> for SqliteError {
+    fn from(value: std::io::Error) -> Self {
+        Self::Io(value)
+    }
+}
+
+impl SqliteError {
+    pub fn class(&self) -> ErrorClass {
+        match self {
+            SqliteError::Io(_) => ErrorClass::Io,
+            SqliteError::NotSqlite | SqliteError::Corrupt(_) => ErrorClass::Corruption,
+            SqliteError::Unsupported(_) => ErrorClass::Constraint,
+            SqliteError::NoSuchTable(_) => ErrorClass::Parse,
+            SqliteError::Page(err) => err.class(),
+            SqliteError::CorruptRow(_, err) => err.class(),
+        }
+    }
+}
+
+impl std::fmt::Display for SqliteError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SqliteError::Io(err) => write!(f, "IO Error: {err}"),
+            SqliteError::NotSqlite => write!(f, "Not a SQLite 3 database file."),
+            SqliteError::Unsupported(what) => write!(f, "Unsupported SQLite file: {what}."),
+            SqliteError::Corrupt(what) => write!(f, "Corrupt SQLite file: {what}."),
+            SqliteError::NoSuchTable(table) => write!(f, "No such table: '{table}'."),
+            SqliteError::Page(err) => write!(f, "{err}"),
+            SqliteError::CorruptRow(row, err) => write!(f, "Row {row} is corrupt: {err}."),
+        }
+    }
+}
+
+fn corrupt(what: impl Into<String>) -> SqliteError {
+    SqliteError::Corrupt(what.into())
+}
+
+pub enum Value {
+    Null,
+    Integer(i64),
+    Real(f64),
+    Text(String),
+    Blob(Vec<u8>),
+}
+
+impl std::fmt::Display for Value {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            Value::Null => Ok(()),
+            Value::Integer(value) => write!(f, "{value}"),
+            // Debug keeps the fraction of whole numbers, as in `2.0`.
+            Value::Real(value) => write!(f, "{value:?}"),
+            Value::Text(value) => write!(f, "{value}"),
+            Value::Blob(bytes) => {
+                write!(f, "X'")?;
+                for byte in bytes {
+                    write!(f, "{byte:02X}")?;
+                }
+                write!(f, "'")
+            }
+        }
+    }
+}
+
+pub struct SqliteColumn {
+    pub name: String,
+    /// Declared with REAL affinity, so whole numbers SQLite stored as
+    /// integers to save space read back as reals.
+    real: bool,
+}
+
+/// A table listed in the file's schema.
+pub struct SqliteTable {
+    pub name: String,
+    pub sql: String,
+    root: u32,
+}
+
+impl SqliteTable {
+    /// The columns from the `CREATE TABLE` statement, and which of them, if
+    /// any, is an alias for the rowid and so is not stored in the record.
+    pub fn columns(&self) -> Result<(Vec<SqliteColumn>, Option<usize>), SqliteError> {
+        let unsupported =
+            || SqliteError::Unsupported(format!("table '{}' is not plain", self.name));
+        let (Some(open), Some(close)) = (self.sql.find('('), self.sql.rfind(')')) else {
+            return Err(unsupported());
+        };
+        if open > close {
+            return Err(unsupported());
+        }
+        if self.sql[close..].to_ascii_uppercase().contains("WITHOUT") {
+            return Err(SqliteError::Unsupported(format!(
+                "table '{}' is a WITHOUT ROWID table",
+                self.name
+            )));
+        }
+
+        let mut columns = Vec::new();
+        let mut rowid = None;
+        for definition in split_definitions(&self.sql[open + 1..close]) {
+            if definition.is_empty() {
+                continue;
+            }
+            let (name, rest) = split_name(definition);
+            let keyword = name.to_ascii_uppercase();
+            if ["CONSTRAINT", "PRIMARY", "UNIQUE", "CHECK", "FOREIGN"].contains(&keyword.as_str()) {
+                continue;
+            }
+            let rest = rest.to_ascii_uppercase();
+            let words = rest.split_whitespace().collect::<Vec<_>>();
+            if words.first() == Some(&"INTEGER")
+                && words.windows(2).any(|pair| pair == ["PRIMARY", "KEY"])
+                && !words.contains(&"DESC")
+            {
+                rowid = Some(columns.len());
+            }
+            let declared = words.first().copied().unwrap_or("");
+            columns.push(SqliteColumn {
+                name: unquote(name),
+                real: ["REAL", "FLOA", "DOUB"]
+                    .iter()
+                    .any(|t| declared.contains(t)),
+            });
+        }
+        Ok((columns, rowid))
+    }
+}
+
+/// Splits the body of a `CREATE TABLE` statement at commas that are not
+/// inside parentheses or quotes.
+fn split_definitions(body: &str) -> Vec<&str> {
+    let mut definitions = Vec::new();
+    let mut depth = 0usize;
+    let mut quote = None;
+    let mut start = 0;
+    for (i, c) in body.char_indices() {
+        match (quote, c) {
+            (Some(end), c) if c == end => quote = None,
+            (Some(_), _) => {}
+            (None, '\'' | '"' | '`') => quote = Some(c),
+            (None, '[') => quote = Some(']'),
+            (None, '(') => depth += 1,
+            (None, ')') => depth = depth.saturating_sub(1),
+            (None, ',') if depth == 0 => {
+                definitions.push(body[start..i].trim());
+                start = i + 1;
+            }
+            _ => {}
+        }
+    }
+    definitions.push(body[start..].trim());
+    definitions
+}
+
+/// Splits a column definition into its possibly quoted name and the rest.
+fn split_name(definition: &str) -> (&str, &str) {
+    let end = match definition.chars().next() {
+        Some(open @ ('"' | '`' | '[')) => {
+            let close = if open == '[' { ']' } else { open };
+            definition[1..]
+                .find(close)
+                .map_or(definition.len(), |i| i + 2)
+        }
+        _ => definition
+            .find(|c: char| c.is_whitespace() || c == '(')
+            .unwrap_or(definition.len()),
+    };
+    definition.split_at(end)
+}
+
+/// Strips the quotes from a quoted name, or returns it as it is when it is
+/// not quoted or its closing quote is missing.
+fn unquote(name: &str) -> String {
+    let quoted = [('"', '"'), ('`', '`'), ('[', ']')]
+        .into_iter()
+        .find_map(|(open, close)| name.strip_prefix(open)?.strip_suffix(close));
+    quoted.unwrap_or(name).to_string()
+}
+
+pub struct SqliteFile {
+    bytes: Vec<u8>,
+    page_size: usize,
+    usable_size: usize,
+}
+
+impl SqliteFile {
+    pub fn open(path: &str) -> Result<Self, SqliteError> {
+        let bytes = std::fs::read(path)?;
+        if bytes.len() < HEADER_SIZE || &bytes[..16] != MAGIC {
+            return Err(SqliteError::NotSqlite);
+        }
+        let page_size = match u16::from_be_bytes([bytes[16], bytes[17]]) {
+            1 => 65536,
+            size => size as usize,
+        };
+        if page_size < 512 || !page_size.is_power_of_two() {
+            return Err(corrupt(format!("page size {page_size}")));
+        }
+        let reserved = bytes[20] as usize;
+        let usable_size = page_size - reserved;
+        if usable_size < 480 {
+            return Err(corrupt(format!("{reserved} reserved bytes per page")));
+        }
+        match u32::from_be_bytes([bytes[56], bytes[57], bytes[58], bytes[59]]) {
+            // Zero means the schema is still empty, which can only be UTF-8.
+            0 | 1 => {}
+            _ => return Err(SqliteError::Unsupported("text is UTF-16".to_string())),
+        }
+
+        Ok(Self {
+            bytes,
+            page_size,
+            usable_size,
+        })
+    }
+
+    /// The tables in the file, in the order they were created, leaving out
+    /// SQLite's own `sqlite_` tables.
+    pub fn tables(&self) -> Result<Vec<SqliteTable>, SqliteError> {
+        let mut tables = Vec::new();
+        for (_, record) in self.records(1)? {
+            let mut values = record.into_iter();
+            let mut next = || values.next().unwrap_or(Value::Null);
+            let (kind, name, _, root, sql) = (next(), next(), next(), next(), next());
+            let (Value::Text(kind), Value::Text(name)) = (kind, name) else {
+                return Err(corrupt("schema entry without a type or name"));
+            };
+            if kind != "table" || name.starts_with("sqlite_") {
+                continue;
+            }
+            let (Value::Integer(root), Value::Text(sql)) = (root, sql) else {
+                // Virtual tables have no B-tree of their own.
+                continue;
+            };
+            let root = u32::try_from(root)
+                .map_err(|_| corrupt(format!("table '{name}' has root page {root}")))?;
+            tables.push(SqliteTable { name, sql, root });
+        }
+        Ok(tables)
+    }
+
+    pub fn table(&self, name: &str) -> Result<SqliteTable, SqliteError> {
+        self.tables()?
+            .into_iter()
+            .find(|table| table.name.eq_ignore_ascii_case(name))
+            .ok_or_else(|| SqliteError::NoSuchTable(name.to_string()))
+    }
+
+    /// The table's rows in rowid order, one value per column, with the rowid
+    /// filled in for a column that aliases it.
+    pub fn rows(&self, table: &SqliteTable) -> Result<Vec<Vec<Value>>, SqliteError> {
+        let (columns, rowid) = table.columns()?;
+        let mut rows = Vec::new();
+        for (key, mut record) in self.records(table.root)? {
+            // Columns added by ALTER TABLE are missing from older records.
+            record.resize_with(columns.len(), || Value::Null);
+            if let Some(i) = rowid {
+                record[i] = Value::Integer(key);
+            }
+            for (value, column) in record.iter_mut().zip(&columns) {
+                if let (Value::Integer(integer), true) = (&value, column.real) {
+                    *value = Value::Real(*integer as f64);
+                }
+            }
+            rows.push(record);
+        }
+        Ok(rows)
+    }
+
+    fn page(&self, number: u32) -> Result<&[u8], SqliteError> {
+        let start = (number as usize)
+            .checked_sub(1)
+            .map(|index| index * self.page_size)
+            .filter(|&start| start + self.page_size <= self.bytes.len())
+            .ok_or_else(|| corrupt(format!("page {number} is out of range")))?;
+        Ok(&self.bytes[start..start + self.page_size])
+    }
+
+    /// Walks the table B-tree rooted at `root` and decodes every record in it.
+    fn records(&self, root: u32) -> Result<Vec<(i64, Vec<Value>)>, SqliteError> {
+        let mut records = Vec::new();
+        let mut stack = vec![root];
+        let mut visited = HashSet::new();
+        while let Some(number) = stack.pop() {
+            if !visited.insert(number) {
+                return Err(corrupt(format!("page {number} is linked twice")));
+            }
+            let page = self.page(number)?;
+            // Page 1 starts with the file header.
+            let header = if number == 1 { HEADER_SIZE } else { 0 };
+            let kind = page[header];
+            let cells = u16::from_be_bytes([page[header + 3], page[header + 4]]) as usize;
+            let pointers = header + if kind == 0x05 { 12 } else { 8 };
+            let cell = |i: usize| -> Result<&[u8], SqliteError> {
+                let at = pointers + 2 * i;
+                let offset = page
+                    .get(at..at + 2)
+                    .map(|b| u16::from_be_bytes([b[0], b[1]]) as usize)
+                    .filter(|&offset| offset < self.usable_size)
+                    .ok_or_else(|| corrupt(format!("page {number} has a bad cell pointer")))?;
+                Ok(&page[offset..self.usable_size])
+            };
+
+            match kind {
+                0x05 => {
+                    let right =
+                        u32::from_be_bytes(page[header + 8..header + 12].try_into().unwrap());
+                    // Pushed in reverse so the leftmost child is visited first.
+                    stack.push(right);
+                    for i in (0..cells).rev() {
+                        let cell = cell(i)?;
+                        let child = cell.get(..4).ok_or_else(|| {
+                            corrupt(format!("page {number} has a truncated cell"))
+                        })?;
+                        stack.push(u32::from_be_bytes(child.try_into().unwrap()));
+                    }
+                }
+                0x0d => {
+                    for i in 0..cells {
+                        let payload = self.payload(cell(i)?, number)?;
+                        let (key, payload) = payload;
+                        records.push((key, decode_record(&payload, number)?));
+                    }
+                }
+                0x02 | 0x0a => {
+                    return Err(SqliteError::Unsupported(format!(
+                        "page {number} belongs to an index"
+                    )));
+                }
+                _ => return Err(corrupt(format!("page {number} has type {kind}"))),
+            }
+        }
+        Ok(records)
+    }
+
+    /// Reads a leaf cell's rowid and its payload, following overflow pages.
+    fn payload(&self, cell: &[u8], number: u32) -> Result<(i64, Vec<u8>), SqliteError> {
+        let truncated = || corrupt(format!("page {number} has a truncated cell"));
+        let (size, used) = varint(cell).ok_or_else(truncated)?;
+        let (key, used_key) = varint(&cell[used..]).ok_or_else(truncated)?;
+        let cell = &cell[used + used_key..];
+        let size = usize::try_from(size)
+            .ok()
+            .filter(|&size| size <= self.bytes.len())
+            .ok_or_else(|| corrupt(format!("page {number} has a record of {size} bytes")))?;
+
+        let usable = self.usable_size;
+        let max_local = usable - 35;
+        let local = if size <= max_local {
+            size
+        } else {
+            let min_local = (usable - 12) * 32 / 255 - 23;
+            let local = min_local + (size - min_local) % (usable - 4);
+            if local <= max_local { local } else { min_local }
+        };
+
+        let mut payload = cell.get(..local).ok_or_else(truncated)?.to_vec();
+        if local < size {
+            let mut next = cell
+                .get(local..local + 4)
+                .map(|b| u32::from_be_bytes(b.try_into().unwrap()))
+                .ok_or_else(truncated)?;
+            let mut visited = HashSet::new();
+            while payload.len() < size {
+                if !visited.insert(next) {
+                    return Err(corrupt(format!("overflow page {next} is linked twice")));
+                }
+                let page = self.page(next)?;
+                let wanted = (size - payload.len()).min(usable - 4);
+                payload.extend_from_slice(&page[4..4 + wanted]);
+                next = u32::from_be_bytes(page[..4].try_into().unwrap());
+            }
+        }
+        Ok((key as i64, payload))
+    }
+}
+
+/// Decodes a SQLite varint: up to nine bytes, big-endian, seven bits per byte
+/// with the high bit set on all but the last, which uses all eight. Returns
+/// the value and the number of bytes it took.
+fn varint(bytes: &[u8]) -> Option<(u64, usize)> {
+    let mut value = 0u64;
+    for (i, &byte) in bytes.iter().enumerate().take(9) {
+        if i == 8 {
+            return Some(((value << 8) | byte as u64, 9));
+        }
+        value = (value << 7) | (byte & 0x7f) as u64;
+        if byte & 0x80 == 0 {
+            return Some((value, i + 1));
+        }
+    }
+    None
+}
+
+fn decode_record(payload: &[u8], number: u32) -> Result<Vec<Value>, SqliteError> {
+    let bad = || corrupt(format!("page {number} has a malformed record"));
+    let (header_size, mut at) = varint(payload).ok_or_else(bad)?;
+    let header_size = usize::try_from(header_size)
+        .ok()
+        .filter(|&size| size <= payload.len())
+        .ok_or_else(bad)?;
+
+    let mut body = header_size;
+    let mut values = Vec::new();
+    while at < header_size {
+        let (serial, used) = varint(&payload[at..header_size]).ok_or_else(bad)?;
+        at += used;
+
+        let size = match serial {
+            0 | 8 | 9 => 0,
+            1..=4 => serial as usize,
+            5 => 6,
+            6 | 7 => 8,
+            10 | 11 => return Err(bad()),
+            _ => ((serial - 12) / 2) as usize,
+        };
+        let bytes = payload.get(body..body + size).ok_or_else(bad)?;
+        body += size;
+
+        let integer = || {
+            // Sign-extend from the first byte.
+            let fill = if bytes[0] & 0x80 != 0 { 0xff } else { 0 };
+            let mut full = [fill; 8];
+            full[8 - size..].copy_from_slice(bytes);
+            i64::from_be_bytes(full)
+        };
+        values.push(match serial {
+            0 => Value::Null,
+            1..=6 => Value::Integer(integer()),
+            7 => Value::Real(f64::from_be_bytes(bytes.try_into().unwrap())),
+            8 => Value::Integer(0),
+            9 => Value::Integer(1),
+            _ if serial % 2 == 0 => Value::Blob(bytes.to_vec()),
+            _ => Value::Text(String::from_utf8_lossy(bytes).into_owned()),
+        });
+    }
+    Ok(values)
+}
+
+const PAGE_SIZE: usize = 4096;
+const LEAF_TABLE: u8 = 0x0d;
+const INTERIOR_TABLE: u8 = 0x05;
+
+/// Writes the rows into a new SQLite file at `path` as a table laid out like
+/// `schema`, returning how many were written. The file must not exist yet.
+pub fn export<'a>(
+    path: &str,
+    schema: &TableSchema,
+    rows: impl Iterator<Item = RowRef<'a>>,
+) -> Result<usize, SqliteError> {
+    // Rows get rowids from 1 in file order. Ids need not be unique, so they
+    // are stored as an ordinary column rather than as the rowid.
+    let mut cells = Vec::new();
+    for (index, row) in rows.enumerate() {
+        row.validate()
+            .map_err(|e| SqliteError::CorruptRow(index, e))?;
+        let values = schema
+            .columns
+            .iter()
+            .zip(row.values())
+            .map(|(column, value)| match column.ctype {
+                ColumnType::Integer => Value::Integer(value.parse().unwrap_or_default()),
+                ColumnType::Varchar(_) => Value::Text(value.into_owned()),
+            })
+            .collect::<Vec<_>>();
+        let rowid = index as i64 + 1;
+        cells.push((rowid, leaf_cell(rowid, &encode_record(&values))));
+    }
+    let count = cells.len();
+
+    // Page 1 holds the schema, which needs the table's root page number, so
+    // it is filled in last.
+    let mut pages = vec![Vec::new()];
+    let mut level = write_leaves(&mut pages, cells);
+    while level.len() > 1 {
+        level = write_interior(&mut pages, &level);
+    }
+    let root = match level.first() {
+        Some(&(page, _)) => page,
+        None => {
+            pages.push(btree_page(LEAF_TABLE, 0, &[], None));
+            pages.len() as u32
+        }
+    };
+
+    let sql = schema.create_sql();
+    let entry = encode_record(&[
+        Value::Text("table".to_string()),
+        Value::Text(schema.name.clone()),
+        Value::Text(schema.name.clone()),
+        Value::Integer(root as i64),
+        Value::Text(sql.trim_end_matches(';').to_string()),
+    ]);
+    pages[0] = btree_page(LEAF_TABLE, HEADER_SIZE, &[leaf_cell(1, &entry)], None);
+    let page_count = pages.len() as u32;
+    write_header(&mut pages[0], page_count);
+
+    let mut file = std::fs::OpenOptions::new()
+        .write(true)
+        .create_new(true)
+        .open(path)?;
+    for page in &pages {
+        file.write_all(page)?;
+    }
+    file.sync_all()?;
+    Ok(count)
+}
+
+fn write_header(page: &mut [u8], page_count: u32) {
+    page[..16].copy_from_slice(MAGIC);
+    page[16..18].copy_from_slice(&(PAGE_SIZE as u16).to_be_bytes());
+    // Format version 1 on both counts: a rollback journal, not a WAL.
+    page[18] = 1;
+    page[19] = 1;
+    // The payload fractions, which the format fixes at these values.
+    page[21] = 64;
+    page[22] = 32;
+    page[23] = 32;
+    // The change counter. Its copy at 92 vouches for the page count at 28.
+    page[24..28].copy_from_slice(&1u32.to_be_bytes());
+    page[28..32].copy_from_slice(&page_count.to_be_bytes());
+    // Schema cookie, schema format 4, and UTF-8 text.
+    page[40..44].copy_from_slice(&1u32.to_be_bytes());
+    page[44..48].copy_from_slice(&4u32.to_be_bytes());
+    page[56..60].copy_from_slice(&1u32.to_be_bytes());
+    page[92..96].copy_from_slice(&1u32.to_be_bytes());
+    page[96..100].copy_from_slice(&3_000_000u32.to_be_bytes());
+}
+
+/// Fills leaf pages with cells in key order, returning each page's number
+/// with the last key on it.
+fn write_leaves(pages: &mut Vec<Vec<u8>>, cells: Vec<(i64, Vec<u8>)>) -> Vec<(u32, i64)> {
+    let mut written = Vec::new();
+    let mut page = Vec::new();
+    let mut used = 8;
+    let mut last_key = 0;
+    for (key, cell) in cells {
+        if used + 2 + cell.len() > PAGE_SIZE {
+            pages.push(btree_page(LEAF_TABLE, 0, &page, None));
+            written.push((pages.len() as u32, last_key));
+            page.clear();
+            used = 8;
+        }
+        used += 2 + cell.len();
+        page.push(cell);
+        last_key = key;
+    }
+    if !page.is_empty() {
+        pages.push(btree_page(LEAF_TABLE, 0, &page, None));
+        written.push((pages.len() as u32, last_key));
+    }
+    written
+}
+
+/// Writes interior pages over `children`, returning the pages of the new
+/// level the way `write_leaves` does. Every child but a page's last gets a
+/// cell with its last key; the last one is the page's right-most pointer.
+fn write_interior(pages: &mut Vec<Vec<u8>>, children: &[(u32, i64)]) -> Vec<(u32, i64)> {
+    let mut written = Vec::new();
+    let mut cells = Vec::new();
+    let mut used = 12;
+    for (i, &(child, key)) in children.iter().enumerate() {
+        let is_last = i + 1 == children.len();
+        let cell = [&child.to_be_bytes()[..], &encode_varint(key as u64)].concat();
+        if is_last || used + 2 + cell.len() > PAGE_SIZE {
+            pages.push(btree_page(INTERIOR_TABLE, 0, &cells, Some(child)));
+            written.push((pages.len() as u32, key));
+            cells.clear();
+            used = 12;
+        } else {
+            used += 2 + cell.len();
+            cells.push(cell);
+        }
+    }
+    written
+}
+
+/// Builds a B-tree page: the header at `start`, the cell pointers after it,
+/// and the cells packed against the end of the page.
+fn btree_page(kind: u8, start: usize, cells: &[Vec<u8>], right: Option<u32>) -> Vec<u8> {
+    let mut page = vec![0u8; PAGE_SIZE];
+    let mut content = PAGE_SIZE;
+    let mut pointer = start + if right.is_some() { 12 } else { 8 };
+    for cell in cells {
+        content -= cell.len();
+        page[content..content + cell.len()].copy_from_slice(cell);
+        page[pointer..pointer + 2].copy_from_slice(&(content as u16).to_be_bytes());
+        pointer += 2;
+    }
+
+    page[start] = kind;
+    page[start + 3..start + 5].copy_from_slice(&(cells.len() as u16).to_be_bytes());
+    page[start + 5..start + 7].copy_from_slice(&(content as u16).to_be_bytes());
+    if let Some(right) = right {
+        page[start + 8..start + 12].copy_from_slice(&right.to_be_bytes());
+    }
+    page
+}
+
+/// A table leaf cell. Rows are far smaller than a page, so the whole payload
+/// always fits in the cell and no overflow pages are needed.
+fn leaf_cell(rowid: i64, payload: &[u8]) -> Vec<u8> {
+    debug_assert!(payload.len() <= PAGE_SIZE - 35);
+    let mut cell = encode_varint(payload.len() as u64);
+    cell.extend(encode_varint(rowid as u64));
+    cell.extend_from_slice(payload);
+    cell
+}
+
+fn encode_record(values: &[Value]) -> Vec<u8> {
+    let mut types = Vec::new();
+    let mut body = Vec::new();
+    for value in values {
+        let serial = match value {
+            Value::Null => 0,
+            Value::Integer(0) => 8,
+            Value::Integer(1) => 9,
+            &Value::Integer(integer) => {
+                let (serial, size) = match integer {
+                    -0x80..0x80 => (1, 1),
+                    -0x8000..0x8000 => (2, 2),
+                    -0x80_0000..0x80_0000 => (3, 3),
+                    -0x8000_0000..0x8000_0000 => (4, 4),
+                    -0x8000_0000_0000..0x8000_0000_0000 => (5, 6),
+                    _ => (6, 8),
+                };
+                body.extend_from_slice(&integer.to_be_bytes()[8 - size..]);
+                serial
+            }
+            Value::Real(real) => {
+                body.extend_from_slice(&real.to_be_bytes());
+                7
+            }
+            Value::Text(text) => {
+                body.extend_from_slice(text.as_bytes());
+                text.len() as u64 * 2 + 13
+            }
+            Value::Blob(bytes) => {
+                body.extend_from_slice(bytes);
+                bytes.len() as u64 * 2 + 12
+            }
+        };
+        types.extend(encode_varint(serial));
+    }
+
+    // The header size counts its own varint, which is one byte for any
+    // header shorter than 128 bytes.
+    let mut record = encode_varint(types.len() as u64 + 1);
+    if record.len() > 1 {
+        record = encode_varint(types.len() as u64 + 2);
+    }
+    record.extend(types);
+    record.extend(body);
+    record
+}
+
+fn encode_varint(value: u64) -> Vec<u8> {
+    if value >> 56 != 0 {
+        // Nine bytes: eight of seven bits, then one of the low eight bits.
+        let mut bytes = (0..8)
+            .map(|i| ((value >> (8 + 7 * (7 - i))) & 0x7f) as u8 | 0x80)
+            .collect::<Vec<_>>();
+        bytes.push(value as u8);
+        return bytes;
+    }
+    let mut bytes = Vec::new();
+    let mut rest = value;
+    loop {
+        bytes.push((rest & 0x7f) as u8 | if bytes.is_empty() { 0 } else { 0x80 });
+        rest >>= 7;
+        if rest == 0 {
+            break;
+        }
+    }
+    bytes.reverse();
+    bytes
+}
@@ -0,0 +1,135 @@
+use crate::error_class::ErrorClass;
+use crate::meta::dump;
+use crate::output::csv_quote;
+use crate::pager::{PageError, Table};
+use crate::sqlite::{self, SqliteError, SqliteFile};
+use std::io::Write;
+use std::path::Path;
+
+/// Subcommands that run a single task on a database file and exit, for use
+/// from scripts: `rlite <name> <db>`.
+pub const SUBCOMMANDS: [&str; 5] = ["check", "compact", "dump", "export-sqlite", "read-sqlite"];
+
+/// Runs a subcommand and returns the process exit code.
+pub fn run(name: &str, args: &[String]) -> i32 {
+    let (db, other) = match (name, args) {
+        ("export-sqlite", [db, out]) => (db, Some(out.as_str())),
+        ("export-sqlite", _) => {
+            println!("Usage: rlite {name} <db> <out.sqlite>");
+            return 1;
+        }
+        (_, [db]) => (db, None),
+        ("read-sqlite", [db, table]) => (db, Some(table.as_str())),
+        ("read-sqlite", _) => {
+            println!("Usage: rlite {name} <file.sqlite> [table]");
+            return 1;
+        }
+        _ => {
+            println!("Usage: rlite {name} <db>");
+            return 1;
+        }
+    };
+    if !Path::new(db).is_file() {
+        println!("No such database file: '{db}'.");
+        return ErrorClass::Io.exit_code();
+    }
+
+    match name {
+        "check" => finish(check(db), PageError::class),
+        "compact" => finish(compact(db), PageError::class),
+        "dump" => finish(dump_db(db), PageError::class),
+        "export-sqlite" => finish(export_sqlite(db, other.unwrap()), SqliteError::class),
+        "read-sqlite" => finish(read_sqlite(db, other), SqliteError::class),
+        _ => unreachable!("not a subcommand: {name}"),
+    }
+}
+
+fn finish<E: std::fmt::Display>(result: Result<i32, E>, class: fn(&E) -> ErrorClass) -> i32 {
+    result.unwrap_or_else(|e| {
+        println!("{e}");
+        class(&e).exit_code()
+    })
+}
+
+/// Prints `ok` for a sound file, or each problem found and exits with the
+/// corruption exit code.
+fn check(db: &str) -> Result<i32, PageError> {
+    let problems = Table::check(db)?;
+    if problems.is_empty() {
+        println!("ok");
+        return Ok(0);
+    }
+    for problem in &problems {
+        println!("{problem}");
+    }
+    Ok(ErrorClass::Corruption.exit_code())
+}
+
+/// Rewrites the database from its rows, dropping anything else in the file.
+fn compact(db: &str) -> Result<i32, PageError> {
+    let before = std::fs::metadata(db)?.len();
+    let compacted = format!("{db}.compact");
+    let _ = std::fs::remove_file(&compacted);
+
+    let rows = Table::new(db)?.backup_to(&compacted)?;
+    std::fs::rename(&compacted, db)?;
+
+    let after = std::fs::metadata(db)?.len();
+    println!("Compacted {rows} rows: {before} bytes -> {after} bytes.");
+    Ok(0)
+}
+
+fn dump_db(db: &str) -> Result<i32, PageError> {
+    let mut table = Table::open_readonly(db)?;
+    let schema = table.schema().clone();
+    match dump(&schema, table.rows()?, &mut std::io::stdout().lock()) {
+        // Piped into something like `head` that stopped reading.
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(0),
+        result => Ok(result.map(|()| 0)?),
+    }
+}
+
+/// Copies the table into a new SQLite file.
+fn export_sqlite(db: &str, out: &str) -> Result<i32, SqliteError> {
+    let mut table = Table::open_readonly(db)?;
+    let schema = table.schema().clone();
+    let rows = sqlite::export(out, &schema, table.rows()?)?;
+    println!("Exported {rows} rows to '{out}'.");
+    Ok(0)
+}
+
+/// Lists the tables in a SQLite file with their `CREATE TABLE` statements, or
+/// prints one table's rows as CSV with a header, ready for `.import`.
+fn read_sqlite(path: &str, table: Option<&str>) -> Result<i32, SqliteError> {
+    let file = SqliteFile::open(path)?;
+    let mut out = std::io::stdout().lock();
+    let result = match table {
+        None => file
+            .tables()?
+            .iter()
+            .try_for_each(|table| writeln!(out, "{};", table.sql)),
+        Some(name) => {
+            let table = file.table(name)?;
+            let (columns, _) = table.columns()?;
+            let rows = file.rows(&table)?;
+            let header = columns
+                .iter()
+                .map(|c| csv_quote(&c.name, ","))
+                .collect::<Vec<_>>();
+            writeln!(out, "{}", header.join(",")).and_then(|()| {
+                rows.iter().try_for_each(|row| {
+                    let fields = row
+                        .iter()
+                        .map(|value| csv_quote(&value.to_string(), ",").into_owned())
+                        .collect::<Vec<_>>();
+                    writeln!(out, "{}", fields.join(","))
+                })
+            })
+        }
+    };
+    match result {
+        // Piped into something like `head` that stopped reading.
+        Err(e) if e.kind() == std::io::ErrorKind::BrokenPipe => Ok(0),
+        result => Ok(result.map(|()| 0)?),
+    }
+}
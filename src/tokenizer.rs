@@ -1,31 +1,61 @@
-use crate::constants::{
-    COLUMN_EMAIL_SIZE, COLUMN_USERNAME_SIZE, EMAIL_OFFSET, EMAIL_SIZE, ID_OFFSET, ID_SIZE,
-    ROW_SIZE, USERNAME_OFFSET, USERNAME_SIZE,
-};
+use crate::error_class::ErrorClass;
+use crate::schema::{ColumnType, TableSchema};
+use std::borrow::Cow;
+use std::io::Write;
 
-pub enum MetaCommandError<'a> {
-    UnrecognizedCommand { meta: &'a str, input: &'a str },
+pub enum PrepareError<'a> {
+    UnrecognizedStatement {
+        statement: Cow<'a, str>,
+        input: &'a str,
+    },
+    InvalidInput {
+        input: &'a str,
+    },
+    InvalidInteger {
+        column: String,
+        value: Cow<'a, str>,
+        input: &'a str,
+    },
+    NegativeNumber {
+        column: String,
+        value: Cow<'a, str>,
+        input: &'a str,
+    },
+    TooLong {
+        column: String,
+        max: usize,
+        value: Cow<'a, str>,
+        input: &'a str,
+    },
+    NulCharacter {
+        value: Cow<'a, str>,
+        input: &'a str,
+    },
+    InvalidEmail {
+        email: Cow<'a, str>,
+        input: &'a str,
+    },
+    TrailingInput {
+        token: Cow<'a, str>,
+        input: &'a str,
+    },
 }
 
-impl std::fmt::Display for MetaCommandError<'_> {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+impl PrepareError<'_> {
+    pub fn class(&self) -> ErrorClass {
         match self {
-            MetaCommandError::UnrecognizedCommand { meta, input } => {
-                write!(f, "Unrecognized command: '{meta}' in '{input}'")
-            }
+            PrepareError::UnrecognizedStatement { .. }
+            | PrepareError::InvalidInput { .. }
+            | PrepareError::InvalidInteger { .. }
+            | PrepareError::NegativeNumber { .. }
+            | PrepareError::TrailingInput { .. } => ErrorClass::Parse,
+            PrepareError::TooLong { .. }
+            | PrepareError::NulCharacter { .. }
+            | PrepareError::InvalidEmail { .. } => ErrorClass::Constraint,
         }
     }
 }
 
-pub enum PrepareError<'a> {
-    UnrecognizedStatement { statement: &'a str, input: &'a str },
-    InvalidInput { input: &'a str },
-    InvalidId { id: &'a str, input: &'a str },
-    NegativeNumber { id: &'a str, input: &'a str },
-    UsernameTooLong { username: &'a str, input: &'a str },
-    EmailTooLong { email: &'a str, input: &'a str },
-}
-
 impl std::fmt::Display for PrepareError<'_> {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
         match self {
@@ -35,165 +65,558 @@ impl std::fmt::Display for PrepareError<'_> {
             PrepareError::InvalidInput { input } => {
                 write!(f, "Invalid input: '{input}'.")
             }
-            PrepareError::InvalidId { id, input } => {
+            PrepareError::InvalidInteger {
+                column,
+                value,
+                input,
+            } => {
                 write!(
                     f,
-                    "Invalid id: '{id}' in '{input}'.\nId has to be a positive integer."
+                    "Invalid {column}: '{value}' in '{input}'.\n{} has to be a positive integer.",
+                    capitalize(column)
                 )
             }
-            PrepareError::NegativeNumber { id, input } => {
+            PrepareError::NegativeNumber {
+                column,
+                value,
+                input,
+            } => {
                 write!(
                     f,
-                    "Invalid id: '{id} in '{input}'.\nId has to be a positive integer."
+                    "Invalid {column}: '{value} in '{input}'.\n{} has to be a positive integer.",
+                    capitalize(column)
                 )
             }
-            PrepareError::UsernameTooLong { username, input } => {
+            PrepareError::TooLong {
+                column,
+                max,
+                value,
+                input,
+            } => {
                 write!(
                     f,
-                    "Invalid username: '{}' in '{}'.\nMaximum valid size: {}.\nUsername's size: {}",
-                    username,
-                    input,
-                    COLUMN_USERNAME_SIZE,
-                    username.len()
+                    "Invalid {column}: '{value}' in '{input}'.\nMaximum valid size: {max} bytes.\n{}'s size: {}",
+                    capitalize(column),
+                    describe_size(value)
                 )
             }
-            PrepareError::EmailTooLong { email, input } => {
+            PrepareError::InvalidEmail { email, input } => {
                 write!(
                     f,
-                    "Invalid email: '{}' in '{}'.\nMaximum valid size: {}.\nEmail's size: {}",
-                    email,
-                    input,
-                    COLUMN_EMAIL_SIZE,
-                    email.len()
+                    "Invalid email: '{email}' in '{input}'.\nExpected a single '@' between a non-empty local part and domain.\nUse '.emailcheck off' to store any text."
+                )
+            }
+            PrepareError::TrailingInput { token, input } => {
+                write!(
+                    f,
+                    "Unexpected '{token}' after the end of the statement in '{input}'.\nUse '.permissive on' to ignore trailing input."
+                )
+            }
+            PrepareError::NulCharacter { value, input } => {
+                write!(
+                    f,
+                    "Invalid value: '{}' in '{input}'.\nValues cannot contain NUL characters.",
+                    value.escape_debug()
                 )
             }
         }
     }
 }
 
-pub fn do_meta_command(input: &str) -> Result<(), MetaCommandError<'_>> {
-    if input.starts_with(".exit") {
-        return Ok(());
+fn capitalize(name: &str) -> String {
+    let mut chars = name.chars();
+    chars.next().map_or(String::new(), |first| {
+        first.to_uppercase().chain(chars).collect()
+    })
+}
+
+/// A value's size in bytes, which is what the column limits count, along with
+/// its length in characters when multibyte characters make them differ.
+fn describe_size(value: &str) -> String {
+    let chars = value.chars().count();
+    if chars == value.len() {
+        format!("{} bytes", value.len())
+    } else {
+        format!("{} bytes ({chars} characters)", value.len())
     }
+}
+
+/// Why a row slot could not be read back as a row.
+#[derive(Debug)]
+pub enum DeserializeError {
+    /// The slot is not exactly one row long.
+    WrongSize { size: usize, expected: usize },
+    /// A text column is not valid UTF-8, or has bytes after its padding starts.
+    InvalidText { column: String },
+}
 
-    let meta = input.split_whitespace().next().unwrap_or_default();
-    Err(MetaCommandError::UnrecognizedCommand { meta, input })
+impl DeserializeError {
+    pub fn class(&self) -> ErrorClass {
+        ErrorClass::Corruption
+    }
+}
+
+impl std::fmt::Display for DeserializeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            DeserializeError::WrongSize { size, expected } => {
+                write!(f, "row is {size} bytes, expected {expected}")
+            }
+            DeserializeError::InvalidText { column } => {
+                write!(f, "{column} is not padded UTF-8")
+            }
+        }
+    }
+}
+
+/// Settings that change how statements are validated.
+#[derive(Clone, Copy)]
+pub struct PrepareOptions {
+    /// Reject emails that don't look like `local@domain`.
+    pub check_email: bool,
+    /// Reject input left over after a complete statement instead of ignoring it.
+    pub strict: bool,
+}
+
+impl Default for PrepareOptions {
+    fn default() -> Self {
+        Self {
+            check_email: true,
+            strict: true,
+        }
+    }
 }
 
 pub enum StatementType {
     Insert,
     Select,
+    /// `select count(*)`.
+    Count,
 }
 
+/// A row in its serialized form, ready to be copied into a page.
 pub struct Row {
-    id: u32,
-    username: [u8; COLUMN_USERNAME_SIZE],
-    email: [u8; COLUMN_EMAIL_SIZE],
+    bytes: Vec<u8>,
 }
 
 impl Row {
-    pub fn deserialize(bytes: &[u8]) -> Self {
-        let mut id_bytes = [0u8; ID_SIZE];
-        let mut username_bytes = [0u8; USERNAME_SIZE];
-        let mut email_bytes = [0u8; EMAIL_SIZE];
+    pub fn serialize(&self) -> &[u8] {
+        &self.bytes
+    }
 
-        id_bytes.copy_from_slice(&bytes[ID_OFFSET..ID_OFFSET + ID_SIZE]);
-        username_bytes.copy_from_slice(&bytes[USERNAME_OFFSET..USERNAME_OFFSET + USERNAME_SIZE]);
-        email_bytes.copy_from_slice(&bytes[EMAIL_OFFSET..EMAIL_OFFSET + EMAIL_SIZE]);
-        let id = u32::from_le_bytes(id_bytes);
+    /// Builds a row from its textual values, one per column, validating each.
+    /// `input` is the text the values came from and is only used in errors.
+    pub fn from_values<'a>(
+        values: &[Cow<'a, str>],
+        input: &'a str,
+        options: &PrepareOptions,
+        schema: &TableSchema,
+    ) -> Result<Self, PrepareError<'a>> {
+        if values.len() != schema.columns.len() {
+            return Err(PrepareError::InvalidInput { input });
+        }
+        let columns = schema.columns.iter().zip(values);
+        let mut bytes = vec![0u8; schema.row_size()];
 
-        Self {
-            id,
-            username: username_bytes,
-            email: email_bytes,
+        for (column, value) in columns.clone() {
+            if column.ctype != ColumnType::Integer {
+                continue;
+            }
+            let column_name = || column.name.clone();
+            if value.starts_with('-') {
+                return Err(PrepareError::NegativeNumber {
+                    column: column_name(),
+                    value: value.clone(),
+                    input,
+                });
+            }
+            let integer = value
+                .parse::<u32>()
+                .map_err(|_| PrepareError::InvalidInteger {
+                    column: column_name(),
+                    value: value.clone(),
+                    input,
+                })?;
+            bytes[column.range()].copy_from_slice(&integer.to_le_bytes());
+        }
+
+        let text = columns.filter(|(column, _)| column.ctype != ColumnType::Integer);
+        // NUL is the padding byte, so a value containing one would read back cut short.
+        if let Some((_, value)) = text.clone().find(|(_, value)| value.contains('\0')) {
+            return Err(PrepareError::NulCharacter {
+                value: value.clone(),
+                input,
+            });
         }
+        for (column, value) in text {
+            if value.len() > column.size() {
+                return Err(PrepareError::TooLong {
+                    column: column.name.clone(),
+                    max: column.size(),
+                    value: value.clone(),
+                    input,
+                });
+            }
+            if column.name == "email" && options.check_email && !is_email(value) {
+                return Err(PrepareError::InvalidEmail {
+                    email: value.clone(),
+                    input,
+                });
+            }
+            bytes[column.offset..column.offset + value.len()].copy_from_slice(value.as_bytes());
+        }
+
+        Ok(Self { bytes })
     }
+}
 
-    pub fn serialize(&self) -> [u8; ROW_SIZE] {
-        let mut bytes = [0u8; ROW_SIZE];
+/// A borrowed view of a serialized row, for reading rows straight out of a
+/// page without copying them into a `Row`.
+#[derive(Clone, Copy)]
+pub struct RowRef<'a> {
+    bytes: &'a [u8],
+    schema: &'a TableSchema,
+}
 
-        let id = self.id.to_le_bytes();
-        bytes[ID_OFFSET..ID_OFFSET + ID_SIZE].copy_from_slice(&id);
-        bytes[USERNAME_OFFSET..USERNAME_OFFSET + USERNAME_SIZE].copy_from_slice(&self.username);
-        bytes[EMAIL_OFFSET..EMAIL_OFFSET + EMAIL_SIZE].copy_from_slice(&self.email);
+impl<'a> RowRef<'a> {
+    /// Wraps a row slot, which must be exactly one row long.
+    pub fn new(bytes: &'a [u8], schema: &'a TableSchema) -> Result<Self, DeserializeError> {
+        if bytes.len() != schema.row_size() {
+            return Err(DeserializeError::WrongSize {
+                size: bytes.len(),
+                expected: schema.row_size(),
+            });
+        }
+        Ok(Self { bytes, schema })
+    }
 
-        bytes
+    /// A column's value as text. Text columns come without their padding and
+    /// only allocate for invalid UTF-8.
+    pub fn value(&self, index: usize) -> Cow<'a, str> {
+        let column = &self.schema.columns[index];
+        let bytes = &self.bytes[column.range()];
+        match column.ctype {
+            ColumnType::Integer => {
+                let mut integer = [0u8; 4];
+                integer.copy_from_slice(bytes);
+                u32::from_le_bytes(integer).to_string().into()
+            }
+            ColumnType::Varchar(_) => String::from_utf8_lossy(trim_padding(bytes)),
+        }
+    }
+
+    /// The slot as stored, padding included.
+    pub fn as_bytes(&self) -> &'a [u8] {
+        self.bytes
+    }
+
+    pub fn values(&self) -> Vec<Cow<'a, str>> {
+        (0..self.schema.columns.len())
+            .map(|i| self.value(i))
+            .collect()
+    }
+
+    /// Checks that the slot holds what `serialize` writes. The accessors
+    /// never fail; they show damaged text with replacement characters.
+    pub fn validate(&self) -> Result<(), DeserializeError> {
+        validate(self.bytes, self.schema)
+    }
+
+    /// Whether the slot is all zeros, as it is before a row is written to it.
+    pub fn is_blank(&self) -> bool {
+        self.bytes.iter().all(|&b| b == 0)
+    }
+
+    pub fn insert_statement(&self) -> String {
+        let values = self
+            .schema
+            .columns
+            .iter()
+            .zip(self.values())
+            .map(|(column, value)| match column.ctype {
+                ColumnType::Integer => value,
+                ColumnType::Varchar(_) => quote(&value).into_owned().into(),
+            })
+            .collect::<Vec<_>>();
+        format!("insert {};", values.join(" "))
     }
 
-    pub fn print(&self) {
-        let username = String::from_utf8_lossy(&self.username);
-        let email = String::from_utf8_lossy(&self.email);
-        println!("({} {} {})", self.id, username, email);
+    /// Writes the row in the original `(id username email)` format.
+    pub fn print_to(&self, out: &mut impl Write) -> std::io::Result<()> {
+        writeln!(out, "({})", self.values().join(" "))
     }
 }
 
-// Username and Email has to be ASCII character to fit in their size
-// 32b and 255b al input has to be 1 char = 1 byte
+/// Quotes a value so that `InputBuffer` and `Tokens` read it back as a single token.
+fn quote(value: &str) -> Cow<'_, str> {
+    let needs_quotes = value.is_empty()
+        || value.contains(char::is_whitespace)
+        || value.contains(';')
+        || value.starts_with(['\'', '"'])
+        || value.starts_with("--");
+    if !needs_quotes {
+        return value.into();
+    }
+
+    // Prefer the quote that needs no escaping, and double it otherwise.
+    let quote = if value.contains('\'') && !value.contains('"') {
+        '"'
+    } else {
+        '\''
+    };
+    let escaped = value.replace(quote, &format!("{quote}{quote}"));
+    format!("{quote}{escaped}{quote}").into()
+}
+
+/// Collects lines of input into complete commands.
+///
+/// Meta commands end with their line, while statements run until a `;` that
+/// is not inside a quoted value, so they can span several lines. Lines are
+/// kept as they are, so quoted values keep their whitespace and line breaks,
+/// except for `--` comments, which start a token and run to the end of the line.
+#[derive(Default)]
+pub struct InputBuffer {
+    pending: String,
+    start_line: usize,
+    /// The quote the pending text ends inside of, if any.
+    quote: Option<char>,
+}
+
+impl InputBuffer {
+    pub fn is_empty(&self) -> bool {
+        self.pending.trim().is_empty()
+    }
+
+    /// Adds a line of input and returns the commands it completes, each with
+    /// the line number it started on.
+    pub fn push_line(&mut self, line: &str, line_number: usize) -> Vec<(usize, String)> {
+        if self.is_empty() {
+            self.pending.clear();
+            let line = line.trim();
+            if line.starts_with('.') {
+                return vec![(line_number, line.to_string())];
+            }
+            self.start_line = line_number;
+        }
+
+        let mut commands = Vec::new();
+        let mut chars = line.chars().peekable();
+        while let Some(c) = chars.next() {
+            match self.quote {
+                // Doubled, the quote stands for itself and the value goes on.
+                Some(q) if c == q && chars.peek() == Some(&q) => {
+                    self.pending.push(c);
+                    chars.next();
+                }
+                Some(q) if c == q => self.quote = None,
+                Some(_) => {}
+                // Quotes only group a value at the start of a token, as in `Tokens`.
+                None if is_token_start(&self.pending) && (c == '\'' || c == '"') => {
+                    self.quote = Some(c);
+                }
+                // A comment runs to the end of the line.
+                None if is_token_start(&self.pending) && c == '-' && chars.peek() == Some(&'-') => {
+                    break;
+                }
+                None if c == ';' => {
+                    let statement = self.pending.trim();
+                    if !statement.is_empty() {
+                        commands.push((self.start_line, statement.to_string()));
+                    }
+                    self.pending.clear();
+                    self.start_line = line_number;
+                    continue;
+                }
+                None => {}
+            }
+            self.pending.push(c);
+        }
+        self.pending.push('\n');
+
+        commands
+    }
+
+    /// Returns a statement still missing its `;` when input ends.
+    pub fn take_incomplete(&mut self) -> Option<(usize, String)> {
+        let pending = std::mem::take(&mut self.pending);
+        self.quote = None;
+        let statement = pending.trim();
+        (!statement.is_empty()).then(|| (self.start_line, statement.to_string()))
+    }
+}
+
+/// Whether the next character starts a token: the text so far is empty or
+/// ends in whitespace.
+fn is_token_start(text: &str) -> bool {
+    text.chars().next_back().is_none_or(char::is_whitespace)
+}
+
+/// Splits statement text on whitespace, keeping quoted values together.
+pub struct Tokens<'a> {
+    rest: &'a str,
+}
+
+impl<'a> Tokens<'a> {
+    pub fn new(input: &'a str) -> Self {
+        Self { rest: input }
+    }
+
+    /// The text not yet split into tokens.
+    pub fn rest(&self) -> &'a str {
+        self.rest.trim()
+    }
+}
+
+impl<'a> Iterator for Tokens<'a> {
+    /// Borrowed unless the value had doubled quotes to undo.
+    type Item = Cow<'a, str>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let rest = self.rest.trim_start();
+        let quote = rest.chars().next()?;
+
+        if (quote == '\'' || quote == '"')
+            && let Some(len) = closing_quote(&rest[1..], quote)
+        {
+            self.rest = &rest[len + 2..];
+            let value = &rest[1..len + 1];
+            let doubled = format!("{quote}{quote}");
+            return Some(if value.contains(&doubled) {
+                value.replace(&doubled, &quote.to_string()).into()
+            } else {
+                value.into()
+            });
+        }
+
+        let len = rest.find(char::is_whitespace).unwrap_or(rest.len());
+        self.rest = &rest[len..];
+        Some(rest[..len].into())
+    }
+}
+
+/// Where the quote that ends a quoted value is, skipping doubled quotes.
+fn closing_quote(text: &str, quote: char) -> Option<usize> {
+    let mut chars = text.char_indices();
+    while let Some((i, c)) = chars.next() {
+        if c == quote {
+            if !text[i + 1..].starts_with(quote) {
+                return Some(i);
+            }
+            chars.next();
+        }
+    }
+    None
+}
+
+/// A deliberately loose check: one `@` with something on either side.
+fn is_email(email: &str) -> bool {
+    match email.split_once('@') {
+        Some((local, domain)) => !local.is_empty() && !domain.is_empty() && !domain.contains('@'),
+        None => false,
+    }
+}
+
+/// Checks that each text column of a row slot is UTF-8 followed only by
+/// padding.
+fn validate(bytes: &[u8], schema: &TableSchema) -> Result<(), DeserializeError> {
+    for column in &schema.columns {
+        if column.ctype == ColumnType::Integer {
+            continue;
+        }
+        let value = &bytes[column.range()];
+        let text = trim_padding(value);
+        if value[text.len()..].iter().any(|&b| b != 0) || std::str::from_utf8(text).is_err() {
+            return Err(DeserializeError::InvalidText {
+                column: column.name.clone(),
+            });
+        }
+    }
+    Ok(())
+}
+
+fn trim_padding(bytes: &[u8]) -> &[u8] {
+    let len = bytes.iter().position(|&b| b == 0).unwrap_or(bytes.len());
+    &bytes[..len]
+}
+
 pub struct Statement {
     pub stype: StatementType,
     pub row: Option<Row>,
+    /// Prefixed with `explain analyze`: run it, but report timings instead of rows.
+    pub analyze: bool,
 }
 
 impl Statement {
     /// Parses a statement from input text.
     ///
     /// Expected format:
-    /// `insert <id> <username> <email>`
+    /// `insert <value> ...`, with one value per column of `schema`
+    /// (`insert <id> <username> <email>` for `users`)
     /// or
     /// `select`
-    pub fn prepare_statement(input: &str) -> Result<Self, PrepareError<'_>> {
-        let mut parts = input.split_whitespace();
+    /// or
+    /// `select count(*)`
+    ///
+    /// Either may be prefixed with `explain analyze`.
+    ///
+    /// Values containing whitespace can be wrapped in single or double quotes,
+    /// with the quote itself doubled inside, as in `'it''s'`.
+    /// The terminating `;` is removed by `InputBuffer` before this is called.
+    pub fn prepare_statement<'a>(
+        input: &'a str,
+        options: &PrepareOptions,
+        schema: &TableSchema,
+    ) -> Result<Self, PrepareError<'a>> {
+        let mut parts = Tokens::new(input);
         let statement = parts.next().ok_or(PrepareError::InvalidInput { input })?;
 
+        if statement == "explain" {
+            if parts.next().as_deref() != Some("analyze") {
+                return Err(PrepareError::UnrecognizedStatement { statement, input });
+            }
+            // Only one level: `explain analyze explain analyze ...` is not a statement.
+            let explained = parts.rest();
+            if let Some(nested) = Tokens::new(explained).next().filter(|t| t == "explain") {
+                return Err(PrepareError::UnrecognizedStatement {
+                    statement: nested,
+                    input,
+                });
+            }
+            let mut explained = Self::prepare_statement(explained, options, schema)?;
+            explained.analyze = true;
+            return Ok(explained);
+        }
+
         let row = if statement == "insert" {
-            let id = parts
-                .next()
-                .ok_or(PrepareError::InvalidInput { input })
-                .and_then(|v| {
-                    if v.starts_with('-') {
-                        return Err(PrepareError::NegativeNumber { id: v, input });
-                    }
-                    v.parse::<u32>()
-                        .map_err(|_| PrepareError::InvalidId { id: v, input })
-                })?;
-            let username = parts
-                .next()
-                .ok_or(PrepareError::InvalidInput { input })
-                .and_then(|s| {
-                    let mut bytes = [0u8; USERNAME_SIZE];
-                    if s.len() > COLUMN_USERNAME_SIZE {
-                        return Err(PrepareError::UsernameTooLong { username: s, input });
-                    }
-                    bytes[..s.len()].copy_from_slice(s.as_bytes());
-                    Ok(bytes)
-                })?;
-            let email = parts
-                .next()
-                .ok_or(PrepareError::InvalidInput { input })
-                .and_then(|s| {
-                    let mut bytes = [0u8; EMAIL_SIZE];
-                    if s.len() > COLUMN_EMAIL_SIZE {
-                        return Err(PrepareError::EmailTooLong { email: s, input });
-                    }
-                    bytes[..s.len()].copy_from_slice(s.as_bytes());
-                    Ok(bytes)
-                })?;
+            let values = (0..schema.columns.len())
+                .map(|_| parts.next().ok_or(PrepareError::InvalidInput { input }))
+                .collect::<Result<Vec<_>, _>>()?;
 
-            Some(Row {
-                id,
-                username,
-                email,
-            })
+            Some(Row::from_values(&values, input, options, schema)?)
         } else {
             None
         };
 
-        let stype = match statement {
+        let stype = match &*statement {
             "insert" => StatementType::Insert,
+            "select" if Tokens::new(parts.rest()).next().as_deref() == Some("count(*)") => {
+                parts.next();
+                StatementType::Count
+            }
             "select" => StatementType::Select,
             _ => return Err(PrepareError::UnrecognizedStatement { statement, input }),
         };
 
-        Ok(Self { stype, row })
+        if options.strict
+            && let Some(token) = parts.next()
+        {
+            return Err(PrepareError::TrailingInput { token, input });
+        }
+
+        Ok(Self {
+            stype,
+            row,
+            analyze: false,
+        })
     }
 }
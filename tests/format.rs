@@ -0,0 +1,180 @@
+//! Golden-file tests for the on-disk format.
+//!
+//! The databases under `tests/fixtures` were written by rlite and are checked
+//! in as they are, so these tests fail if a change stops existing files from
+//! opening or makes rlite write different bytes for the same rows.
+
+mod common;
+
+use common::{PAGE_SIZE, ROW_SIZE, copy_fixture, fixture, scratch_dir};
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+const THREE_ROWS: &str = "insert 1 alice alice@example.com; \
+                          insert 258 josé jose@example.com; \
+                          insert 4294967295 'bob smith' bob@example.com";
+
+fn run(db: &Path, commands: &[&str]) -> Output {
+    let args = [&["--init", "/dev/null", db.to_str().unwrap()][..], commands].concat();
+    common::rlite(db.parent().unwrap(), &args, b"")
+}
+
+/// Runs commands against a database that must succeed, and returns what rlite printed.
+fn rlite(db: &Path, commands: &[&str]) -> String {
+    let output = run(db, commands);
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert!(output.status.success(), "rlite failed:\n{stdout}");
+    stdout
+}
+
+fn two_hundred_rows() -> String {
+    (1..=200)
+        .map(|i| format!("insert {i} user{i} user{i}@example.com;"))
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+#[test]
+fn reads_rows_from_fixture() {
+    let db = copy_fixture("three_rows.db", "reads_rows_from_fixture");
+
+    assert_eq!(
+        rlite(&db, &[".mode csv", "select"]),
+        "1,alice,alice@example.com\n\
+         258,josé,jose@example.com\n\
+         4294967295,bob smith,bob@example.com\n"
+    );
+}
+
+#[test]
+fn counts_rows_across_full_pages() {
+    let db = copy_fixture("two_hundred_rows.db", "counts_rows_across_full_pages");
+
+    let output = rlite(&db, &[".mode csv", "select"]);
+    // 14 full pages, which are written with their padding, then 4 rows.
+    assert_eq!(output.lines().count(), 200);
+    assert_eq!(
+        output.lines().last(),
+        Some("200,user200,user200@example.com")
+    );
+}
+
+#[test]
+fn appends_after_full_pages() {
+    let db = copy_fixture("two_hundred_rows.db", "appends_after_full_pages");
+
+    rlite(&db, &["insert 201 user201 user201@example.com"]);
+    let output = rlite(&db, &[".mode csv", "select"]);
+    assert_eq!(output.lines().count(), 201);
+    assert_eq!(
+        fs::metadata(&db).unwrap().len() as usize,
+        14 * PAGE_SIZE + 5 * ROW_SIZE
+    );
+}
+
+#[test]
+fn writes_fixtures_byte_for_byte() {
+    let dir = scratch_dir("writes_fixtures_byte_for_byte");
+
+    for (name, inserts) in [
+        ("three_rows.db", THREE_ROWS.to_string()),
+        ("two_hundred_rows.db", two_hundred_rows()),
+    ] {
+        let db = dir.join(name);
+        rlite(&db, &[&inserts]);
+        assert!(
+            fs::read(&db).unwrap() == fs::read(fixture(name)).unwrap(),
+            "{name} differs from the fixture"
+        );
+    }
+}
+
+#[test]
+fn stores_rows_in_documented_layout() {
+    let bytes = fs::read(fixture("three_rows.db")).unwrap();
+    assert_eq!(bytes.len(), 3 * ROW_SIZE);
+
+    let row = &bytes[ROW_SIZE..2 * ROW_SIZE];
+    assert_eq!(row[..4], 258u32.to_le_bytes());
+    assert_eq!(&row[4..9], "josé".as_bytes());
+    assert!(row[9..36].iter().all(|&b| b == 0));
+    assert_eq!(&row[36..52], b"jose@example.com");
+    assert!(row[52..].iter().all(|&b| b == 0));
+
+    let row = &bytes[2 * ROW_SIZE..];
+    assert_eq!(row[..4], u32::MAX.to_le_bytes());
+}
+
+#[test]
+fn reports_corrupt_row() {
+    let db = copy_fixture("three_rows.db", "reports_corrupt_row");
+    let mut bytes = fs::read(&db).unwrap();
+    // A byte after the padding starts in the second row's username.
+    bytes[ROW_SIZE + 20] = 0xff;
+    fs::write(&db, bytes).unwrap();
+
+    let output = run(&db, &[".mode csv", "select"]);
+    assert_eq!(output.status.code(), Some(5));
+    assert_eq!(
+        String::from_utf8(output.stdout).unwrap(),
+        "1,alice,alice@example.com\n\
+         Row 1 is corrupt: username is not padded UTF-8.\n"
+    );
+}
+
+#[test]
+fn skips_pages_with_corrupt_rows() {
+    let db = copy_fixture("two_hundred_rows.db", "skips_pages_with_corrupt_rows");
+    let mut bytes = fs::read(&db).unwrap();
+    // Row 15, on the second page.
+    bytes[PAGE_SIZE + ROW_SIZE + 20] = 0xff;
+    fs::write(&db, bytes).unwrap();
+
+    let output = run(&db, &[".pragma skip_corrupt=on", ".mode csv", "select"]);
+    assert!(output.status.success());
+    let stdout = String::from_utf8(output.stdout).unwrap();
+    assert_eq!(stdout.lines().count(), 200 - 14);
+    assert!(stdout.contains("14,user14,") && stdout.contains("29,user29,"));
+    assert!(!stdout.contains("15,user15,") && !stdout.contains("28,user28,"));
+    assert_eq!(
+        String::from_utf8(output.stderr).unwrap(),
+        "Skipping page 1: row 15 is corrupt: username is not padded UTF-8.\n"
+    );
+}
+
+#[test]
+fn recovers_past_zeroed_pages() {
+    let dir = scratch_dir("recovers_past_zeroed_pages");
+    let db = dir.join("zeroed.db");
+    let mut bytes = vec![0; 2 * PAGE_SIZE];
+    bytes.extend_from_slice(&fs::read(fixture("three_rows.db")).unwrap()[..ROW_SIZE]);
+    fs::write(&db, bytes).unwrap();
+
+    assert_eq!(
+        rlite(&db, &[".recover copy.db"]),
+        "Recovered 1 rows to 'copy.db'; skipped 2 of 3 pages.\n"
+    );
+    assert_eq!(
+        rlite(&dir.join("copy.db"), &[".mode csv", "select"]),
+        "1,alice,alice@example.com\n"
+    );
+}
+
+#[test]
+fn rejects_files_past_the_page_limit() {
+    let db = scratch_dir("rejects_files_past_the_page_limit").join("huge.db");
+    // Sparse, and past what a 32-bit offset can address.
+    fs::File::create(&db)
+        .unwrap()
+        .set_len((1 << 32) + 1)
+        .unwrap();
+
+    let output = run(&db, &["select"]);
+    assert_eq!(output.status.code(), Some(5));
+    assert!(
+        String::from_utf8(output.stdout)
+            .unwrap()
+            .ends_with("File has 1048577 pages, more than the maximum of 100\n")
+    );
+}
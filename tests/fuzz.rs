@@ -0,0 +1,261 @@
+//! Deterministic fuzzing of the statement parser and the file loader.
+//!
+//! Pseudo-random statements and database files are fed to the binary, which
+//! must report errors for them rather than panic, and to the `fuzz_prepare`
+//! and `fuzz_open` entry points the cargo-fuzz harnesses in `fuzz/` run. The
+//! generator is seeded, so a failure reproduces on every run.
+
+mod common;
+
+use common::{PAGE_SIZE, ROW_SIZE, Rng, fixture, rlite, scratch_dir};
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+fn assert_no_panic(output: &Output, input: &dyn std::fmt::Debug) {
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    assert!(
+        output.status.code() != Some(101) && !stderr.contains("panicked"),
+        "rlite panicked on {input:?}:\n{stderr}"
+    );
+}
+
+const TOKENS: &[&str] = &[
+    "insert",
+    "select",
+    "explain",
+    "analyze",
+    ";",
+    "'",
+    "\"",
+    "''",
+    "0",
+    "1",
+    "-1",
+    "4294967295",
+    "4294967296",
+    "99999999999999999999",
+    "a@b",
+    "@",
+    "josé",
+    "\u{0}",
+    "\t",
+    "--",
+];
+
+const META_COMMANDS: &[&str] = &[
+    ".width",
+    ".timeout_ms",
+    ".mode",
+    ".separator",
+    ".headers",
+    ".nullvalue",
+    ".help",
+    ".show",
+    ".schema",
+    ".dump",
+    ".dbinfo",
+    ".constants",
+    ".emailcheck",
+    ".permissive",
+    ".echo",
+    ".timer",
+    ".import",
+    ".read",
+];
+
+const ARGUMENTS: &[&str] = &[
+    "on",
+    "off",
+    "users",
+    "csv",
+    "json",
+    "table",
+    "column",
+    "insert",
+    "0",
+    "1",
+    "18446744073709551615",
+    "99999999999999999999999",
+    "-5",
+    "--separator",
+    "/nonexistent/file",
+    "''",
+    "'a b'",
+];
+
+fn random_line(rng: &mut Rng) -> String {
+    match rng.below(10) {
+        0..=5 => {
+            let len = rng.below(8);
+            let mut words = (0..len).map(|_| rng.pick(TOKENS)).collect::<Vec<_>>();
+            if rng.below(3) == 0 {
+                words.insert(0, "insert");
+            }
+            words.join(" ")
+        }
+        6..=7 => {
+            let mut words = vec![rng.pick(META_COMMANDS)];
+            for _ in 0..rng.below(4) {
+                words.push(rng.pick(ARGUMENTS));
+            }
+            words.join(" ")
+        }
+        8 => "x".repeat(rng.below(600)),
+        _ => {
+            // Arbitrary printable text, kept away from meta commands so it
+            // can't name a file to write.
+            let len = rng.below(80);
+            let text = (0..len)
+                .map(|_| char::from_u32(rng.below(0x250) as u32 + 0x20).unwrap_or('?'))
+                .collect::<String>();
+            text.trim_start_matches('.').to_string()
+        }
+    }
+}
+
+#[test]
+fn random_statements_do_not_panic() {
+    let dir = scratch_dir("random_statements_do_not_panic");
+    let db = dir.join("fuzz.db");
+    let mut rng = Rng(0x5eed_1234_abcd_0001);
+
+    for _ in 0..20 {
+        let script = (0..200)
+            .map(|_| random_line(&mut rng))
+            .collect::<Vec<_>>()
+            .join("\n");
+        let output = rlite(
+            &dir,
+            &["--init", "/dev/null", db.to_str().unwrap()],
+            script.as_bytes(),
+        );
+        assert_no_panic(&output, &script);
+    }
+}
+
+#[test]
+fn deeply_nested_explain_does_not_panic() {
+    let dir = scratch_dir("deeply_nested_explain_does_not_panic");
+    let db = dir.join("fuzz.db");
+    let statement = format!("{}select;\n", "explain analyze ".repeat(100_000));
+
+    let output = rlite(
+        &dir,
+        &["--init", "/dev/null", db.to_str().unwrap()],
+        statement.as_bytes(),
+    );
+    assert_no_panic(&output, &"explain analyze ... select;");
+}
+
+#[test]
+fn huge_width_is_rejected() {
+    let dir = scratch_dir("huge_width_is_rejected");
+    let db = dir.join("fuzz.db");
+    let script = ".mode column\n.width 18446744073709551615\ninsert 1 a a@b;\nselect;\n";
+
+    let output = rlite(
+        &dir,
+        &["--init", "/dev/null", db.to_str().unwrap()],
+        script.as_bytes(),
+    );
+    assert_no_panic(&output, &script);
+    assert!(String::from_utf8_lossy(&output.stdout).contains("Invalid argument"));
+}
+
+/// A file made of real rows, with random damage on top.
+fn random_file(rng: &mut Rng) -> Vec<u8> {
+    let len = match rng.below(10) {
+        0 => 0,
+        1 => (101 + rng.below(3)) * PAGE_SIZE + rng.below(PAGE_SIZE),
+        _ => rng.below(3 * PAGE_SIZE),
+    };
+    let mut bytes = vec![0u8; len];
+    for (i, slot) in bytes.chunks_exact_mut(ROW_SIZE).enumerate() {
+        slot[0] = i as u8;
+        slot[4..20].copy_from_slice(b"user@example.com");
+    }
+    for _ in 0..rng.below(40) {
+        if !bytes.is_empty() {
+            let i = rng.below(bytes.len());
+            bytes[i] = rng.next() as u8;
+        }
+    }
+    bytes
+}
+
+#[test]
+fn random_files_do_not_panic() {
+    let dir = scratch_dir("random_files_do_not_panic");
+    let mut rng = Rng(0x5eed_1234_abcd_0002);
+
+    for i in 0..60 {
+        let bytes = random_file(&mut rng);
+        let db = dir.join(format!("fuzz-{i}.db"));
+        let path = db.to_str().unwrap();
+        let input = format!("{} random bytes", bytes.len());
+
+        for args in [
+            &[
+                "--init",
+                "/dev/null",
+                path,
+                "select",
+                ".dbinfo",
+                "insert 1 a a@b",
+            ][..],
+            &["check", path],
+            &["dump", path],
+            &["compact", path],
+        ] {
+            fs::write(&db, &bytes).unwrap();
+            assert_no_panic(&rlite(&dir, args, b""), &input);
+        }
+    }
+}
+
+#[test]
+fn fuzz_targets_do_not_panic() {
+    let mut rng = Rng(0x5eed_1234_abcd_0004);
+
+    for _ in 0..200 {
+        let script = (0..20)
+            .map(|_| random_line(&mut rng))
+            .collect::<Vec<_>>()
+            .join("\n");
+        rlite::fuzz_prepare(script.as_bytes());
+        rlite::fuzz_open(&random_file(&mut rng));
+    }
+}
+
+#[test]
+fn damaged_sqlite_files_do_not_panic() {
+    let dir = scratch_dir("damaged_sqlite_files_do_not_panic");
+    let original = fs::read(fixture("app.sqlite")).unwrap();
+    let mut rng = Rng(0x5eed_1234_abcd_0003);
+
+    for i in 0..60 {
+        let mut bytes = original.clone();
+        for _ in 0..1 + rng.below(20) {
+            let i = rng.below(bytes.len());
+            bytes[i] = rng.next() as u8;
+        }
+        bytes.truncate(bytes.len() - rng.below(2) * rng.below(bytes.len()));
+        let db = dir.join(format!("fuzz-{i}.sqlite"));
+        fs::write(&db, &bytes).unwrap();
+
+        let input = format!("app.sqlite damaged, try {i}");
+        for table in [&[][..], &["users"], &["order items"]] {
+            let args = [&["read-sqlite", db.to_str().unwrap()][..], table].concat();
+            assert_no_panic(&rlite(&dir, &args, b""), &input);
+        }
+    }
+}
+
+#[test]
+fn database_that_is_a_directory_does_not_panic() {
+    let dir = scratch_dir("database_that_is_a_directory_does_not_panic");
+
+    let output = rlite(&dir, &[dir.to_str().unwrap(), "select"], b"");
+    assert_no_panic(&output, &Path::new(&dir));
+}
@@ -0,0 +1,95 @@
+//! `.import` of JSON files, both as an array of objects and as JSON Lines.
+
+mod common;
+
+use common::{scratch_dir, stdout};
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+fn rlite(db: &Path, commands: &[&str]) -> Output {
+    let args = [&["--init", "/dev/null", db.to_str().unwrap()][..], commands].concat();
+    common::rlite(db.parent().unwrap(), &args, b"")
+}
+
+#[test]
+fn imports_json_array_and_lines() {
+    let dir = scratch_dir("imports_json_array_and_lines");
+    let db = dir.join("test.db");
+    let array = dir.join("users.json");
+    let lines = dir.join("users.jsonl");
+    fs::write(
+        &array,
+        r#"[
+            {"id": 1, "username": "alice", "email": "alice@example.com"},
+            {"EMAIL": "béb@example.com", "id": "2", "username": "b\"ob"}
+        ]"#,
+    )
+    .unwrap();
+    fs::write(
+        &lines,
+        "{\"id\": 3, \"username\": true, \"email\": \"c@d\"}\n\n\
+         {\"id\": 4, \"username\": \"\\ud83d\\ude00\", \"email\": \"e@f\"}\n",
+    )
+    .unwrap();
+
+    let output = rlite(
+        &db,
+        &[
+            &format!(".import {} users --json", array.display()),
+            &format!(".import {} users --json", lines.display()),
+            "select",
+        ],
+    );
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "Imported 2 rows.\n\
+         Imported 2 rows.\n\
+         (1 alice alice@example.com)\n\
+         (2 b\"ob béb@example.com)\n\
+         (3 true c@d)\n\
+         (4 😀 e@f)\n\
+         Executed.\n"
+    );
+}
+
+#[test]
+fn reports_every_bad_json_record() {
+    let dir = scratch_dir("reports_every_bad_json_record");
+    let db = dir.join("test.db");
+    let json = dir.join("bad.json");
+    fs::write(
+        &json,
+        "[{\"id\": 1, \"username\": \"a\", \"email\": \"a@b\"},\n\
+         {\"id\": 2, \"username\": null, \"email\": \"a@b\"},\n\
+         {\"id\": 3, \"username\": \"x\"},\n\
+         {\"id\": 4, \"username\": \"x\", \"email\": \"a@b\", \"age\": 3},\n\
+         7]",
+    )
+    .unwrap();
+    let path = json.display();
+
+    // The valid first record is not imported either.
+    let output = rlite(&db, &[&format!(".import {path} users --json"), "select"]);
+    assert_eq!(output.status.code(), Some(3));
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "{path}:2: 'username' must be a string, number or boolean.\n\
+             {path}:3: missing column 'email'.\n\
+             {path}:4: unknown column 'age'.\n\
+             {path}:5: expected an object.\n\
+             Import aborted: 4 invalid record(s).\n\
+             Executed.\n"
+        )
+    );
+
+    fs::write(&json, "[{\"id\": 1,\n \"username\": \"a\" \"email\": 3}]").unwrap();
+    let output = rlite(&db, &[&format!(".import {path} users --json")]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(
+        stdout(&output),
+        "Invalid JSON on line 2: expected ',' or '}'.\n"
+    );
+}
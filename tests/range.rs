@@ -0,0 +1,83 @@
+//! `Table::range` bounds, read through the library rather than the binary.
+
+mod common;
+
+use common::{ROWS_PER_PAGE, scratch_dir};
+use rlite::pager::Table;
+use rlite::schema::TableSchema;
+use rlite::tokenizer::{PrepareOptions, Row};
+use std::ops::{Bound, RangeBounds};
+
+/// A table of `count` rows whose ids are their positions plus one, so the
+/// ids a range returns show which positions it covered.
+fn table(test: &str, count: usize) -> Table {
+    let schema = TableSchema::users();
+    let rows = (1..=count)
+        .map(|id| {
+            let values = [id.to_string().into(), "user".into(), "a@b".into()];
+            Row::from_values(&values, "", &PrepareOptions::default(), &schema)
+                .unwrap_or_else(|_| panic!("row {id}"))
+        })
+        .collect::<Vec<_>>();
+    let mut table = Table::new(scratch_dir(test).join("test.db")).unwrap();
+    assert_eq!(table.insert_many(&rows).unwrap(), count);
+    table
+}
+
+fn ids(table: &mut Table, rows: impl RangeBounds<usize>) -> Vec<usize> {
+    let cursor = table.range(rows).unwrap();
+    let len = cursor.len();
+    let ids = cursor
+        .map(|row| row.value(0).parse().unwrap())
+        .collect::<Vec<_>>();
+    assert_eq!(ids.len(), len);
+    ids
+}
+
+#[test]
+fn empty_ranges_return_nothing() {
+    let mut table = table("empty_ranges_return_nothing", 20);
+
+    assert_eq!(ids(&mut table, 5..5), Vec::<usize>::new());
+    assert_eq!(
+        ids(&mut table, (Bound::Included(7), Bound::Excluded(3))),
+        Vec::<usize>::new()
+    );
+    assert_eq!(ids(&mut table, 20..), Vec::<usize>::new());
+    assert_eq!(ids(&mut table, 25..30), Vec::<usize>::new());
+
+    let mut empty = self::table("empty_table", 0);
+    assert_eq!(ids(&mut empty, ..), Vec::<usize>::new());
+}
+
+#[test]
+fn ranges_stop_at_the_last_row() {
+    let mut table = table("ranges_stop_at_the_last_row", 20);
+
+    assert_eq!(ids(&mut table, 17..100), vec![18, 19, 20]);
+    assert_eq!(ids(&mut table, 18..=usize::MAX), vec![19, 20]);
+    assert_eq!(ids(&mut table, ..).len(), 20);
+}
+
+#[test]
+fn ranges_cross_page_boundaries() {
+    let mut table = table("ranges_cross_page_boundaries", 3 * ROWS_PER_PAGE);
+    let boundary = ROWS_PER_PAGE;
+
+    assert_eq!(
+        ids(&mut table, boundary - 2..boundary + 2),
+        vec![boundary - 1, boundary, boundary + 1, boundary + 2]
+    );
+    assert_eq!(ids(&mut table, boundary..=boundary), vec![boundary + 1]);
+    assert_eq!(
+        ids(
+            &mut table,
+            (Bound::Excluded(boundary - 1), Bound::Included(boundary))
+        ),
+        vec![boundary + 1]
+    );
+    assert_eq!(
+        ids(&mut table, ..),
+        (1..=3 * ROWS_PER_PAGE).collect::<Vec<_>>()
+    );
+}
@@ -0,0 +1,45 @@
+//! `.read` of script files, including scripts that read other scripts.
+
+mod common;
+
+use common::{scratch_dir, stdout};
+use std::fs;
+use std::path::Path;
+use std::process::Output;
+
+fn rlite(db: &Path, commands: &[&str]) -> Output {
+    let args = [&["--init", "/dev/null", db.to_str().unwrap()][..], commands].concat();
+    common::rlite(db.parent().unwrap(), &args, b"")
+}
+
+#[test]
+fn script_cannot_read_itself() {
+    let dir = scratch_dir("script_cannot_read_itself");
+    let db = dir.join("test.db");
+    fs::write(
+        dir.join("outer.sql"),
+        "insert 1 alice a@b;\n.read inner.sql\ninsert 3 carol c@d;\n",
+    )
+    .unwrap();
+    fs::write(
+        dir.join("inner.sql"),
+        "insert 2 bob b@c;\n.read outer.sql\n",
+    )
+    .unwrap();
+
+    let output = rlite(&db, &[".read outer.sql"]);
+    assert_eq!(output.status.code(), Some(0));
+    assert_eq!(
+        stdout(&output),
+        "Executed.\n\
+         Executed.\n\
+         inner.sql:2: Script 'outer.sql' is already being read.\n\
+         Executed.\n"
+    );
+
+    let output = rlite(&db, &["select"]);
+    assert_eq!(
+        stdout(&output),
+        "(1 alice a@b)\n(2 bob b@c)\n(3 carol c@d)\nExecuted.\n"
+    );
+}
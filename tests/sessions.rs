@@ -0,0 +1,135 @@
+//! Golden-output tests for whole REPL sessions.
+//!
+//! Each file under `tests/sessions` is a script of runs against one fresh
+//! database, each run a separate rlite process, so later runs see what
+//! earlier ones wrote:
+//!
+//! ```text
+//! # Comments and blank lines before the first run describe the session.
+//! -- run [arguments before the database name]
+//! insert 1 alice alice@example.com;
+//! -- expect [exit code, 0 if left out]
+//! Executed.
+//! ```
+//!
+//! The input is piped to rlite with the database named `test.db` in the
+//! current directory, and stdout must match the expected text exactly. Set
+//! `RLITE_BLESS=1` to rewrite the expected sections from what rlite printed
+//! instead, after checking the change is intended.
+
+mod common;
+
+use common::scratch_dir;
+use std::fs;
+use std::path::Path;
+
+struct Run {
+    args: Vec<String>,
+    input: String,
+    expected: String,
+    code: i32,
+}
+
+fn parse(text: &str, path: &Path) -> Vec<Run> {
+    let mut runs = Vec::<Run>::new();
+    let mut in_expect = false;
+    for line in text.split_inclusive('\n') {
+        if let Some(args) = line.strip_prefix("-- run") {
+            runs.push(Run {
+                args: args.split_whitespace().map(str::to_string).collect(),
+                input: String::new(),
+                expected: String::new(),
+                code: 0,
+            });
+            in_expect = false;
+        } else if let Some(code) = line.strip_prefix("-- expect") {
+            let run = runs
+                .last_mut()
+                .unwrap_or_else(|| panic!("{}: '-- expect' before any '-- run'", path.display()));
+            run.code = match code.trim() {
+                "" => 0,
+                code => code
+                    .parse()
+                    .unwrap_or_else(|_| panic!("{}: bad exit code '{code}'", path.display())),
+            };
+            in_expect = true;
+        } else if let Some(run) = runs.last_mut() {
+            if in_expect {
+                run.expected.push_str(line);
+            } else {
+                run.input.push_str(line);
+            }
+        } else if !(line.trim().is_empty() || line.starts_with('#')) {
+            panic!("{}: text before the first '-- run'", path.display());
+        }
+    }
+    runs
+}
+
+/// Writes a session back with each expected section replaced.
+fn render(runs: &[Run], header: &str) -> String {
+    let mut text = header.to_string();
+    for run in runs {
+        let args = run.args.iter().map(|a| format!(" {a}")).collect::<String>();
+        text.push_str(&format!("-- run{args}\n{}", run.input));
+        if !run.input.is_empty() && !run.input.ends_with('\n') {
+            text.push('\n');
+        }
+        match run.code {
+            0 => text.push_str("-- expect\n"),
+            code => text.push_str(&format!("-- expect {code}\n")),
+        }
+        text.push_str(&run.expected);
+    }
+    text
+}
+
+#[test]
+fn sessions_match_golden_output() {
+    let bless = std::env::var_os("RLITE_BLESS").is_some();
+    let sessions = Path::new(env!("CARGO_MANIFEST_DIR")).join("tests/sessions");
+    let mut paths = fs::read_dir(&sessions)
+        .unwrap()
+        .map(|entry| entry.unwrap().path())
+        .filter(|path| path.extension().is_some_and(|e| e == "txt"))
+        .collect::<Vec<_>>();
+    paths.sort();
+    assert!(!paths.is_empty(), "no sessions in {}", sessions.display());
+
+    let mut failures = Vec::new();
+    for path in &paths {
+        let text = fs::read_to_string(path).unwrap();
+        let header = &text[..text.find("-- run").unwrap_or(text.len())];
+        let mut runs = parse(&text, path);
+        let name = path.file_stem().unwrap().to_str().unwrap();
+        let dir = scratch_dir(name);
+
+        for (i, run) in runs.iter_mut().enumerate() {
+            let args = ["--init", "/dev/null"]
+                .into_iter()
+                .chain(run.args.iter().map(String::as_str))
+                .chain(["test.db"])
+                .collect::<Vec<_>>();
+            let output = common::rlite(&dir, &args, run.input.as_bytes());
+            let stdout = String::from_utf8_lossy(&output.stdout).into_owned();
+            let code = output.status.code().unwrap_or(-1);
+
+            if bless {
+                run.expected = stdout;
+                run.code = code;
+            } else if stdout != run.expected || code != run.code {
+                failures.push(format!(
+                    "{name}, run {}: expected exit {}, got {code}\n\
+                     --- expected\n{}--- actual\n{stdout}",
+                    i + 1,
+                    run.code,
+                    run.expected
+                ));
+            }
+        }
+        if bless {
+            fs::write(path, render(&runs, header)).unwrap();
+        }
+    }
+    assert!(failures.is_empty(), "\n{}", failures.join("\n"));
+}
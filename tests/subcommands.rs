@@ -0,0 +1,238 @@
+//! `rlite check`, `rlite dump`, `rlite compact`, `rlite export-sqlite` and
+//! `rlite read-sqlite`, run the way a script would.
+
+mod common;
+
+use common::{ROW_SIZE, copy_fixture, stdout};
+use std::fs;
+use std::process::Output;
+
+/// Runs rlite the way a script would, with no input.
+fn rlite(args: &[&str]) -> Output {
+    common::rlite(&std::env::temp_dir(), args, b"")
+}
+
+#[test]
+fn check_passes_sound_file() {
+    let db = copy_fixture("two_hundred_rows.db", "check_passes_sound_file");
+
+    let output = rlite(&["check", db.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "ok\n");
+}
+
+#[test]
+fn check_fails_damaged_file() {
+    let db = copy_fixture("two_hundred_rows.db", "check_fails_damaged_file");
+    let mut bytes = fs::read(&db).unwrap();
+    bytes[4095] = 0xff;
+    bytes[ROW_SIZE..2 * ROW_SIZE].fill(0);
+    bytes.extend_from_slice(b"xy");
+    fs::write(&db, bytes).unwrap();
+
+    let output = rlite(&["check", db.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(5));
+    assert_eq!(
+        stdout(&output),
+        "row 1 on page 0 is blank\n\
+         page 0 has data after its last row\n\
+         file ends with 2 bytes of a partial row\n"
+    );
+}
+
+#[test]
+fn check_passes_control_characters() {
+    let db = copy_fixture("three_rows.db", "check_passes_control_characters");
+    let db = db.to_str().unwrap();
+    let insert = rlite(&["--init", "/dev/null", db, "insert 4 'tab\there' a@b"]);
+    assert_eq!(stdout(&insert), "Executed.\n");
+
+    let output = rlite(&["check", db]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "ok\n");
+}
+
+#[test]
+fn dump_prints_statements() {
+    let db = copy_fixture("three_rows.db", "dump_prints_statements");
+
+    let output = rlite(&["dump", db.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "-- CREATE TABLE users (id INTEGER, username VARCHAR(32), email VARCHAR(255));\n\
+         insert 1 alice alice@example.com;\n\
+         insert 258 josé jose@example.com;\n\
+         insert 4294967295 'bob smith' bob@example.com;\n"
+    );
+}
+
+#[test]
+fn dump_replays_awkward_values() {
+    let db = copy_fixture("three_rows.db", "dump_replays_awkward_values");
+    let dir = db.parent().unwrap();
+    let (csv, dump, copy) = (
+        dir.join("awkward.csv"),
+        dir.join("dump.sql"),
+        dir.join("copy.db"),
+    );
+    fs::write(
+        &csv,
+        "id,username,email\n\
+         5,\"it's \"\"x\"\" y\",a@b\n\
+         6,\"  two\n  lines  \",c@d\n\
+         7,--dash,'@\"\n\
+         8,semi;colon,e@f\n",
+    )
+    .unwrap();
+
+    let db = db.to_str().unwrap();
+    let import = rlite(&[
+        "--init",
+        "/dev/null",
+        db,
+        &format!(".import {} users", csv.display()),
+    ]);
+    assert_eq!(stdout(&import), "Imported 4 rows.\n");
+    let output = rlite(&["dump", db]);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output)
+            .lines()
+            .skip(4)
+            .collect::<Vec<_>>()
+            .join("\n"),
+        "insert 5 'it''s \"x\" y' a@b;\n\
+         insert 6 '  two\n  lines  ' c@d;\n\
+         insert 7 '--dash' '''@\"';\n\
+         insert 8 'semi;colon' e@f;"
+    );
+
+    fs::write(&dump, &output.stdout).unwrap();
+    let copy = copy.to_str().unwrap();
+    let replay = rlite(&[
+        "--init",
+        "/dev/null",
+        copy,
+        &format!(".read {}", dump.display()),
+    ]);
+    assert!(replay.status.success());
+    assert_eq!(fs::read(copy).unwrap(), fs::read(db).unwrap());
+}
+
+#[test]
+fn compact_drops_stray_bytes() {
+    let db = copy_fixture("three_rows.db", "compact_drops_stray_bytes");
+    let original = fs::read(&db).unwrap();
+    fs::write(&db, [&original[..], b"xy"].concat()).unwrap();
+
+    let output = rlite(&["compact", db.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(fs::read(&db).unwrap(), original);
+}
+
+#[test]
+fn missing_database_fails() {
+    let output = rlite(&["check", "/nonexistent/rlite.db"]);
+    assert_eq!(output.status.code(), Some(4));
+}
+
+#[test]
+fn read_sqlite_lists_tables() {
+    let db = copy_fixture("app.sqlite", "read_sqlite_lists_tables");
+
+    let output = rlite(&["read-sqlite", db.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        "CREATE TABLE users (id integer primary key, username text, email text);\n\
+         CREATE TABLE \"order items\" ([item id] int, \"price\" real, note text, data blob, \
+         added text, unique(note));\n"
+    );
+}
+
+#[test]
+fn read_sqlite_prints_rows_as_csv() {
+    let db = copy_fixture("app.sqlite", "read_sqlite_prints_rows_as_csv");
+
+    let output = rlite(&["read-sqlite", db.to_str().unwrap(), "users"]);
+    assert!(output.status.success());
+    let lines = stdout(&output).lines().collect::<Vec<_>>();
+    assert_eq!(lines.len(), 201);
+    assert_eq!(lines[0], "id,username,email");
+    assert_eq!(lines[1], "1,user1,user1@example.com");
+    assert_eq!(lines[200], "200,user200,user200@example.com");
+
+    // The long note spills onto overflow pages; `added` came from ALTER TABLE.
+    let output = rlite(&["read-sqlite", db.to_str().unwrap(), "order items"]);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        format!(
+            "item id,price,note,data,added\n\
+             1,2.0,,X'00FF',\n\
+             -300000,1.25,\"a, \"\"quoted\"\"\n{}\",,\n\
+             3,,third,,new\n",
+            "x".repeat(3000)
+        )
+    );
+}
+
+#[test]
+fn read_sqlite_rejects_other_files() {
+    let db = copy_fixture("three_rows.db", "read_sqlite_rejects_other_files");
+
+    let output = rlite(&["read-sqlite", db.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(5));
+    assert_eq!(stdout(&output), "Not a SQLite 3 database file.\n");
+
+    let db = copy_fixture("app.sqlite", "read_sqlite_rejects_other_files");
+    let output = rlite(&["read-sqlite", db.to_str().unwrap(), "orders"]);
+    assert_eq!(output.status.code(), Some(2));
+    assert_eq!(stdout(&output), "No such table: 'orders'.\n");
+}
+
+#[test]
+fn read_sqlite_keeps_names_missing_their_closing_quote() {
+    // Its schema was edited to `CREATE TABLE t (a text, [é)` after writing a row.
+    let db = copy_fixture(
+        "unclosed_quote.sqlite",
+        "read_sqlite_keeps_names_missing_their_closing_quote",
+    );
+
+    let output = rlite(&["read-sqlite", db.to_str().unwrap(), "t"]);
+    assert!(output.status.success());
+    assert_eq!(stdout(&output), "a,[é\nx,y\n");
+}
+
+#[test]
+fn export_sqlite_round_trips() {
+    let db = copy_fixture("three_rows.db", "export_sqlite_round_trips");
+    let out = db.with_extension("sqlite");
+
+    let output = rlite(&["export-sqlite", db.to_str().unwrap(), out.to_str().unwrap()]);
+    assert!(output.status.success());
+    assert_eq!(
+        stdout(&output),
+        format!("Exported 3 rows to '{}'.\n", out.display())
+    );
+    assert!(fs::read(&out).unwrap().starts_with(b"SQLite format 3\0"));
+
+    let output = rlite(&["read-sqlite", out.to_str().unwrap()]);
+    assert_eq!(
+        stdout(&output),
+        "CREATE TABLE users (id INTEGER, username VARCHAR(32), email VARCHAR(255));\n"
+    );
+    let output = rlite(&["read-sqlite", out.to_str().unwrap(), "users"]);
+    assert_eq!(
+        stdout(&output),
+        "id,username,email\n\
+         1,alice,alice@example.com\n\
+         258,josé,jose@example.com\n\
+         4294967295,bob smith,bob@example.com\n"
+    );
+
+    // An existing file is never overwritten.
+    let output = rlite(&["export-sqlite", db.to_str().unwrap(), out.to_str().unwrap()]);
+    assert_eq!(output.status.code(), Some(4));
+}